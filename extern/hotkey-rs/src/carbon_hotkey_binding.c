@@ -15,7 +15,7 @@ int hotkey_handler(
     int result = GetEventParameter(event, kEventParamDirectObject, typeEventHotKeyID, NULL, sizeof(event_hotkey), NULL, &event_hotkey);
     if (result == noErr && saved_callback && saved_closure)
     {
-        saved_callback(event_hotkey.id, saved_closure);
+        saved_callback(event_hotkey.id, GetEventKind(event) == kEventHotKeyPressed, saved_closure);
     }
     return noErr;
 }
@@ -26,11 +26,13 @@ void *install_event_handler(HotkeyCallback callback, void *data)
         return NULL;
     saved_callback = callback;
     saved_closure = data;
-    EventTypeSpec event_type;
-    event_type.eventClass = kEventClassKeyboard;
-    event_type.eventKind = kEventHotKeyPressed;
+    EventTypeSpec event_types[2];
+    event_types[0].eventClass = kEventClassKeyboard;
+    event_types[0].eventKind = kEventHotKeyPressed;
+    event_types[1].eventClass = kEventClassKeyboard;
+    event_types[1].eventKind = kEventHotKeyReleased;
     EventHandlerRef handler_ref;
-    int result = InstallEventHandler(GetApplicationEventTarget(), &hotkey_handler, 1, &event_type, data, &handler_ref);
+    int result = InstallEventHandler(GetApplicationEventTarget(), &hotkey_handler, 2, event_types, data, &handler_ref);
 
     if (result == noErr)
     {
@@ -1,4 +1,4 @@
-typedef void (*HotkeyCallback)(int, void *);
+typedef void (*HotkeyCallback)(int, int, void *);
 
 void *install_event_handler(HotkeyCallback callback, void *data);
 int uninstall_event_handler(void *event_handler_ref);
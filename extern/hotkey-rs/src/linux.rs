@@ -1,4 +1,5 @@
 use std::collections::HashMap;
+use std::collections::HashSet;
 use std::mem;
 use std::ptr;
 use std::sync::mpsc;
@@ -111,19 +112,43 @@ impl HotkeyListener for Listener {
                 let mut supported_rtrn: i32 = mem::MaybeUninit::uninit().assume_init();
                 (xlib.XkbSetDetectableAutoRepeat)(display, 1, &mut supported_rtrn);
 
-                (xlib.XSelectInput)(display, root, xlib::KeyReleaseMask);
+                (xlib.XSelectInput)(display, root, xlib::KeyPressMask | xlib::KeyReleaseMask);
                 let mut event: xlib::XEvent = mem::MaybeUninit::uninit().assume_init();
+                // held hotkeys, repeated presses of held keys are ignored
+                let mut pressed: HashSet<ListenerId> = HashSet::new();
                 loop {
                     if (xlib.XPending)(display) > 0 {
                         (xlib.XNextEvent)(display, &mut event);
-                        if let xlib::KeyRelease = event.get_type() {
-                            if let Some((_, handler)) = hotkey_map
-                                .lock()
-                                .unwrap()
-                                .get_mut(&(event.key.keycode as i32, event.key.state))
-                            {
-                                handler();
+                        match event.get_type() {
+                            xlib::KeyPress => {
+                                let id = (event.key.keycode as i32, event.key.state);
+                                if !pressed.contains(&id) {
+                                    if let Some((_, handler)) =
+                                        hotkey_map.lock().unwrap().get_mut(&id)
+                                    {
+                                        pressed.insert(id);
+                                        handler(true);
+                                    }
+                                }
+                            }
+                            xlib::KeyRelease => {
+                                // the modifiers can be released before the key
+                                let keycode = event.key.keycode as i32;
+                                let released: Vec<ListenerId> = pressed
+                                    .iter()
+                                    .filter(|id| id.0 == keycode)
+                                    .copied()
+                                    .collect();
+                                for id in released {
+                                    pressed.remove(&id);
+                                    if let Some((_, handler)) =
+                                        hotkey_map.lock().unwrap().get_mut(&id)
+                                    {
+                                        handler(false);
+                                    }
+                                }
                             }
+                            _ => {}
                         }
                     }
                     match thread_receiver.try_recv() {
@@ -193,9 +218,13 @@ impl HotkeyListener for Listener {
         }
     }
 
-    fn register_hotkey<F>(&mut self, hotkey: ListenerHotkey, handler: F) -> Result<(), HotkeyError>
+    fn register_hotkey_with_release<F>(
+        &mut self,
+        hotkey: ListenerHotkey,
+        handler: F,
+    ) -> Result<(), HotkeyError>
     where
-        F: 'static + FnMut() + Send,
+        F: 'static + FnMut(bool) + Send,
     {
         for (key, _) in self.handlers.lock().unwrap().values() {
             if *key == hotkey {
@@ -101,7 +101,7 @@ pub mod keys {
     pub const KEYPAD_9: u32 = 0x5C;
 }
 
-type KeyCallback = unsafe extern "C" fn(c_int, *mut c_void);
+type KeyCallback = unsafe extern "C" fn(c_int, c_int, *mut c_void);
 
 #[link(name = "carbon_hotkey_binding.a", kind = "static")]
 extern "C" {
@@ -111,24 +111,24 @@ extern "C" {
     fn unregister_hotkey(hotkey_ref: *mut c_void) -> c_int;
 }
 
-unsafe extern "C" fn trampoline<F>(result: c_int, user_data: *mut c_void)
+unsafe extern "C" fn trampoline<F>(result: c_int, pressed: c_int, user_data: *mut c_void)
 where
-    F: FnMut(c_int) + 'static,
+    F: FnMut(c_int, bool) + 'static,
 {
     let user_data = &mut *(user_data as *mut F);
-    user_data(result);
+    user_data(result, pressed != 0);
 }
 
 fn get_trampoline<F>() -> KeyCallback
 where
-    F: FnMut(c_int) + 'static,
+    F: FnMut(c_int, bool) + 'static,
 {
     trampoline::<F>
 }
 
 fn register_event_handler_callback<F>(handler: *mut F) -> *mut c_void
 where
-    F: FnMut(i32) + 'static + Sync + Send,
+    F: FnMut(i32, bool) + 'static + Sync + Send,
 {
     unsafe {
         let cb = get_trampoline::<F>();
@@ -177,9 +177,9 @@ impl HotkeyListener for Listener {
 
         thread::spawn(move || {
             let hotkey_map_clone = hotkey_map.clone();
-            let callback = Box::new(move |id| {
+            let callback = Box::new(move |id, pressed| {
                 if let Some((_, handler, _)) = hotkey_map_clone.lock().unwrap().get_mut(&id) {
-                    handler();
+                    handler(pressed);
                 }
             });
 
@@ -267,9 +267,13 @@ impl HotkeyListener for Listener {
         }
     }
 
-    fn register_hotkey<F>(&mut self, hotkey: ListenerHotkey, handler: F) -> Result<(), HotkeyError>
+    fn register_hotkey_with_release<F>(
+        &mut self,
+        hotkey: ListenerHotkey,
+        handler: F,
+    ) -> Result<(), HotkeyError>
     where
-        F: 'static + FnMut() + Send,
+        F: 'static + FnMut(bool) + Send,
     {
         for (key, _, _) in self.handlers.lock().unwrap().values() {
             if *key == hotkey {
@@ -1,6 +1,7 @@
 use thiserror::Error;
 
-pub type ListenerCallback = dyn 'static + FnMut() + Send;
+/// Called with true when the hotkey is pressed and with false when it is released
+pub type ListenerCallback = dyn 'static + FnMut(bool) + Send;
 
 #[derive(Debug, PartialEq, Eq, Copy, Clone)]
 pub struct ListenerHotkey {
@@ -16,13 +17,31 @@ impl ListenerHotkey {
 
 pub trait HotkeyListener {
     fn new() -> Self;
+    /// Registers a hotkey whose callback is called when it is pressed
     fn register_hotkey<F>(
+        &mut self,
+        hotkey: ListenerHotkey,
+        mut callback: F,
+    ) -> Result<(), HotkeyError>
+    where
+        F: 'static + FnMut() + Send,
+    {
+        self.register_hotkey_with_release(hotkey, move |pressed| {
+            if pressed {
+                callback();
+            }
+        })
+    }
+    /// Registers a hotkey whose callback is called when it is pressed and released
+    ///
+    /// Repeated presses of a held key are only reported once
+    fn register_hotkey_with_release<F>(
         &mut self,
         hotkey: ListenerHotkey,
         callback: F,
     ) -> Result<(), HotkeyError>
     where
-        F: 'static + FnMut() + Send;
+        F: 'static + FnMut(bool) + Send;
     fn unregister_hotkey(&mut self, hotkey: ListenerHotkey) -> Result<(), HotkeyError>;
     fn registered_hotkeys(&self) -> Vec<ListenerHotkey>;
 }
@@ -1,4 +1,5 @@
 use std::collections::HashMap;
+use std::collections::HashSet;
 use std::mem;
 use std::sync::mpsc;
 use std::sync::mpsc::Receiver;
@@ -102,17 +103,33 @@ impl HotkeyListener for Listener {
         let (thread_sender, method_receiver) = mpsc::channel();
 
         thread::spawn(move || unsafe {
+            // held hotkeys, windows only reports presses so the release is polled
+            let mut pressed: HashSet<ListenerId> = HashSet::new();
             loop {
                 let mut msg = mem::MaybeUninit::uninit().assume_init();
                 while winuser::PeekMessageW(&mut msg, 0 as HWND, 0, 0, 1) > 0 {
-                    if msg.wParam != 0 {
+                    if msg.message == winuser::WM_HOTKEY && !pressed.contains(&(msg.wParam as i32))
+                    {
                         if let Some((_, handler)) =
                             hotkey_map.lock().unwrap().get_mut(&(msg.wParam as i32))
                         {
-                            handler();
+                            pressed.insert(msg.wParam as i32);
+                            handler(true);
                         }
                     }
                 }
+                pressed.retain(|id| {
+                    let mut hotkey_map = hotkey_map.lock().unwrap();
+                    let (hotkey, handler) = match hotkey_map.get_mut(id) {
+                        Some(entry) => entry,
+                        None => return false,
+                    };
+                    if winuser::GetAsyncKeyState(hotkey.key as i32) as u16 & 0x8000 != 0 {
+                        return true;
+                    }
+                    handler(false);
+                    false
+                });
                 match thread_receiver.try_recv() {
                     Ok(HotkeyMessage::RegisterHotkey(id, hotkey)) => {
                         let result =
@@ -174,9 +191,13 @@ impl HotkeyListener for Listener {
         }
     }
 
-    fn register_hotkey<F>(&mut self, hotkey: ListenerHotkey, handler: F) -> Result<(), HotkeyError>
+    fn register_hotkey_with_release<F>(
+        &mut self,
+        hotkey: ListenerHotkey,
+        handler: F,
+    ) -> Result<(), HotkeyError>
     where
-        F: 'static + FnMut() + Send,
+        F: 'static + FnMut(bool) + Send,
     {
         for (key, _) in self.handlers.lock().unwrap().values() {
             if *key == hotkey {
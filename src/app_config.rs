@@ -4,7 +4,7 @@ use super::hotkey;
 use super::sound;
 use super::utils;
 use anyhow::{anyhow, Context, Result};
-use clap::{crate_authors, crate_description, crate_version, App, Arg};
+use clap::{crate_authors, crate_description, crate_version, App, Arg, SubCommand};
 use log::{error, info, trace, warn};
 use once_cell::sync::Lazy;
 use paste::paste;
@@ -32,6 +32,24 @@ static GLOBAL_APP_CONFIG: GlobalAppConfig = Lazy::new(|| {
     parking_lot::RwLock::new(std::sync::Arc::new(app_config))
 });
 
+/// Sound to render with the `export` subcommand
+#[derive(Debug, Clone)]
+pub struct ExportCommand {
+    pub soundboard: String,
+    pub sound: String,
+    pub output: PathBuf,
+}
+
+static EXPORT_COMMAND: Lazy<parking_lot::RwLock<Option<ExportCommand>>> =
+    Lazy::new(Default::default);
+
+/// Returns the sound to export if the `export` subcommand was used
+pub fn get_export_command() -> Option<ExportCommand> {
+    // the subcommand is parsed with the app config
+    let _ = get_app_config();
+    EXPORT_COMMAND.read().clone()
+}
+
 fn option_true() -> Option<bool> {
     Some(true)
 }
@@ -81,6 +99,31 @@ macro_rules! make_config {
         ));
     );
 
+    ( @ $name:ident { $param:ident : Map, $($rest:tt)* } -> ($($result:tt)*) ) => (
+        make_config!(@ $name { $($rest)* } -> (
+            $($result)*
+            #[serde(skip_serializing_if = "Option::is_none")]
+            pub $param : Option<std::collections::BTreeMap<String, String>>,
+        ));
+    );
+
+    ( @ $name:ident { $param:ident : f32, $($rest:tt)* } -> ($($result:tt)*) ) => (
+        make_config!(@ $name { $($rest)* } -> (
+            $($result)*
+            #[serde(skip_serializing_if = "Option::is_none")]
+            pub $param : Option<f32>,
+        ));
+    );
+
+    ( @ $name:ident { $param:ident : f32 $default:literal, $($rest:tt)* } -> ($($result:tt)*) ) => (
+        make_config!(@ $name { $($rest)* } -> (
+            $($result)*
+            #[serde(skip_serializing_if = "Option::is_none")]
+            #[serde(default = $default)]
+            pub $param : Option<f32>,
+        ));
+    );
+
     ( $name:ident { $( $param:ident : $type:ident $($default:literal),*),* $(,)* } ) => (
         make_config!(@ $name { $($param : $type $($default),*,)* } -> ());
     );
@@ -94,11 +137,53 @@ fn default_http_socket_addr() -> Option<String> {
     Some("127.0.0.1:8080".to_owned())
 }
 
+/// Target loudness in LUFS used for loudness normalization
+pub const DEFAULT_TARGET_LOUDNESS: f32 = -18.0;
+
+fn default_target_loudness() -> Option<f32> {
+    Some(DEFAULT_TARGET_LOUDNESS)
+}
+
+fn default_noise_gate_threshold() -> Option<f32> {
+    Some(-50.0)
+}
+
+fn default_high_pass_frequency() -> Option<f32> {
+    Some(80.0)
+}
+
+fn default_compressor_threshold() -> Option<f32> {
+    Some(-18.0)
+}
+
+fn default_compressor_ratio() -> Option<f32> {
+    Some(4.0)
+}
+
+fn default_pitch_shift() -> Option<f32> {
+    Some(5.0)
+}
+
+fn default_ducking_amount() -> Option<f32> {
+    Some(12.0)
+}
+
+fn default_ducking_attack() -> Option<f32> {
+    Some(0.05)
+}
+
+fn default_ducking_release() -> Option<f32> {
+    Some(0.5)
+}
+
 make_config!(AppConfig {
     input_device : String,
     output_device : String,
     loopback_device: String,
+    loopback_devices: Map, // additional loop devices by routing target name
     stop_hotkey: String "default_stop_hotkey",
+    push_to_talk_hotkey: String, // the input is streamed to the loop device while held
+    push_to_mute_hotkey: String, // the input is muted on the loop device while held
     http_socket_addr: String "default_http_socket_addr",
     spotify_user: String,
     spotify_pass: String,
@@ -112,8 +197,32 @@ make_config!(AppConfig {
 
     stream_input_to_loop: true,
     simultaneous_playback: true,
+    queue_playback: false,
     auto_loop_device: false,
     embed_web: true,
+    loudness_normalization: false,
+    target_loudness: f32 "default_target_loudness",
+    fade_in: f32,  // default fade in duration in seconds
+    fade_out: f32, // default fade out duration in seconds when stopping a sound
+    output_headroom: f32,   // attenuation of the mixed sounds in dB before the limiter
+    loopback_headroom: f32, // attenuation of the mixed sounds in dB before the limiter
+    mic_ducking: false,
+    ducking_amount: f32 "default_ducking_amount", // attenuation of the input in dB
+    ducking_attack: f32 "default_ducking_attack", // seconds
+    ducking_release: f32 "default_ducking_release", // seconds
+    noise_gate: false,
+    noise_gate_threshold: f32 "default_noise_gate_threshold", // dBFS
+    high_pass: false,
+    high_pass_frequency: f32 "default_high_pass_frequency", // Hz
+    compressor: false,
+    compressor_threshold: f32 "default_compressor_threshold", // dBFS
+    compressor_ratio: f32 "default_compressor_ratio",
+    pitch_shift: f32 "default_pitch_shift", // semitones of the pitch shift voice effect
+    pitch_shift_hotkey: String,
+    robot_hotkey: String,
+    reverb_hotkey: String,
+    echo_hotkey: String,
+    watch_soundboards: true, // reloads the soundboards when their files change
 });
 
 /// Returns the global app config
@@ -129,6 +238,75 @@ pub fn set_stream_input_to_loop_option(option: Option<bool>) {
     *GLOBAL_APP_CONFIG.write() = std::sync::Arc::new(config);
 }
 
+/// Persists the devices chosen at runtime in the config file
+///
+/// Only the device lines of the file are changed, the other settings, values from
+/// command line args or enviroment args and the comments are kept as they are
+pub fn save_devices(devices: &sound::DeviceNames) -> Result<()> {
+    let config_path = get_config_file_path()
+        .context("Failed to get config file path")?
+        .ok_or_else(|| anyhow!("no existing config file path"))?;
+    let mut content = fs::read_to_string(&config_path)
+        .with_context(|| format!("Failed to read_to_string {}", config_path.display()))?;
+    content = set_config_line(&content, "input_device", devices.input.as_deref());
+    content = set_config_line(&content, "output_device", devices.output.as_deref());
+    content = set_config_line(&content, "loopback_device", devices.loopback.as_deref());
+    toml::from_str::<AppConfig>(&content)
+        .with_context(|| format!("Failed to parse changed {}", config_path.display()))?;
+    fs::write(&config_path, content)?;
+    info!("Saved devices in config file {:?}", config_path.display());
+
+    let mut config = (*get_app_config()).clone();
+    config.input_device = devices.input.clone();
+    config.output_device = devices.output.clone();
+    config.loopback_device = devices.loopback.clone();
+    *GLOBAL_APP_CONFIG.write() = std::sync::Arc::new(config);
+    Ok(())
+}
+
+/// Replaces the line of the top level string setting `key` in a toml file
+///
+/// The line is commented out if the value is `None` and added before the first table if missing
+fn set_config_line(content: &str, key: &str, value: Option<&str>) -> String {
+    let is_key_line = |line: &str| {
+        line.trim_start()
+            .strip_prefix(key)
+            .map_or(false, |rest| rest.trim_start().starts_with('='))
+    };
+    let new_line = value.map(|value| {
+        format!(
+            "{} = {}",
+            key,
+            toml::Value::String(value.to_owned()).to_string()
+        )
+    });
+    let mut lines: Vec<String> = Vec::new();
+    let mut found = false;
+    for line in content.lines() {
+        if !found && is_key_line(line) {
+            found = true;
+            match new_line.as_ref() {
+                Some(new_line) => lines.push(new_line.clone()),
+                None => lines.push(format!("# {}", line.trim_start())),
+            }
+        } else {
+            lines.push(line.to_owned());
+        }
+    }
+    if let (false, Some(new_line)) = (found, new_line) {
+        let table_start = lines
+            .iter()
+            .position(|line| line.trim_start().starts_with('['))
+            .unwrap_or_else(|| lines.len());
+        lines.insert(table_start, new_line);
+    }
+    let mut result = lines.join("\n");
+    if content.ends_with('\n') {
+        result.push('\n');
+    }
+    result
+}
+
 /// Reload the app config from a possibly changed config file
 fn reload_app_config_from_disk() -> Result<()> {
     *GLOBAL_APP_CONFIG.write() = std::sync::Arc::new(load_and_merge_app_config()?);
@@ -160,12 +338,26 @@ fn load_and_merge_app_config() -> Result<AppConfig> {
     add_arg!(input_device);
     add_arg!(output_device);
     add_arg!(loopback_device);
+    add_arg!(loopback_devices);
     add_arg!(spotify_user);
     add_arg!(spotify_pass);
     add_arg!(stop_hotkey);
+    add_arg!(push_to_talk_hotkey);
+    add_arg!(push_to_mute_hotkey);
+    add_arg!(pitch_shift_hotkey);
+    add_arg!(robot_hotkey);
+    add_arg!(reverb_hotkey);
+    add_arg!(echo_hotkey);
     add_arg!(print_possible_devices);
     add_arg!(simultaneous_playback);
     add_arg!(stream_input_to_loop);
+    add_arg!(loudness_normalization);
+    add_arg!(queue_playback);
+    add_arg!(mic_ducking);
+    add_arg!(noise_gate);
+    add_arg!(high_pass);
+    add_arg!(compressor);
+    add_arg!(watch_soundboards);
 
     #[cfg(feature = "autoloop")]
     add_arg!(auto_loop_device);
@@ -199,9 +391,16 @@ fn load_and_merge_app_config() -> Result<AppConfig> {
         loopback_device
             .short("l")
             .help("Sets the loopback device to use"),
+        loopback_devices.help("Sets additional loopback devices as routing targets, e.g. \"obs=OBS Cable;discord=CABLE Input\""),
         spotify_user.help("Sets the spotify user name to use spotify as source"),
         spotify_pass.help("Sets the spotify passowrd to use spotify as source"),
         stop_hotkey.help("Sets the stop hotkey to stop all sounds"),
+        push_to_talk_hotkey.help("Sets the hotkey to hold to stream the input to the loop device"),
+        push_to_mute_hotkey.help("Sets the hotkey to hold to mute the input streamed to the loop device"),
+        pitch_shift_hotkey.help("Sets the hotkey to toggle the pitch shift voice effect"),
+        robot_hotkey.help("Sets the hotkey to toggle the robot voice effect"),
+        reverb_hotkey.help("Sets the hotkey to toggle the reverb voice effect"),
+        echo_hotkey.help("Sets the hotkey to toggle the echo voice effect"),
         print_possible_devices
             .short("P")
             .help("Print possible devices")
@@ -213,6 +412,27 @@ fn load_and_merge_app_config() -> Result<AppConfig> {
             .takes_value(true)
             .possible_values(&["true", "false"])
             .help("Enable/disable to stream audio from input device to loopback device"),
+        loudness_normalization
+            .possible_values(&["true", "false"])
+            .help("Enable/disable the normalization of all sounds to the target loudness"),
+        queue_playback
+            .possible_values(&["true", "false"])
+            .help("Enable/disable queueing of sounds which are played while another sound plays"),
+        mic_ducking
+            .possible_values(&["true", "false"])
+            .help("Enable/disable attenuating the input streamed to the loop device while sounds play on it"),
+        noise_gate
+            .possible_values(&["true", "false"])
+            .help("Enable/disable the noise gate on the input streamed to the loop device"),
+        high_pass
+            .possible_values(&["true", "false"])
+            .help("Enable/disable the high pass filter on the input streamed to the loop device"),
+        compressor
+            .possible_values(&["true", "false"])
+            .help("Enable/disable the compressor and limiter on the input streamed to the loop device"),
+        watch_soundboards
+            .possible_values(&["true", "false"])
+            .help("Enable/disable reloading the soundboards when their files change"),
     ]);
 
     #[cfg(feature = "autoloop")]
@@ -263,8 +483,35 @@ fn load_and_merge_app_config() -> Result<AppConfig> {
         );
     }
 
+    #[cfg(feature = "wav")]
+    {
+        matches = matches.subcommand(
+            SubCommand::with_name("export")
+                .about("Renders a sound with its trims and effects to a wav file")
+                .args(&[
+                    Arg::with_name("soundboard")
+                        .required(true)
+                        .help("Name of the soundboard"),
+                    Arg::with_name("sound")
+                        .required(true)
+                        .help("Name of the sound"),
+                    Arg::with_name("output")
+                        .required(true)
+                        .help("Path of the wav file"),
+                ]),
+        );
+    }
+
     let arguments = matches.get_matches();
 
+    if let Some(export) = arguments.subcommand_matches("export") {
+        *EXPORT_COMMAND.write() = Some(ExportCommand {
+            soundboard: export.value_of("soundboard").unwrap_or_default().to_owned(),
+            sound: export.value_of("sound").unwrap_or_default().to_owned(),
+            output: PathBuf::from(export.value_of("output").unwrap_or_default()),
+        });
+    }
+
     macro_rules! merge_option_with_args_and_env {
         ($name:ident) => {
             merge_option_with_args_and_env(
@@ -279,11 +526,23 @@ fn load_and_merge_app_config() -> Result<AppConfig> {
     merge_option_with_args_and_env!(output_device);
     merge_option_with_args_and_env!(loopback_device);
     merge_option_with_args_and_env!(stop_hotkey);
+    merge_option_with_args_and_env!(push_to_talk_hotkey);
+    merge_option_with_args_and_env!(push_to_mute_hotkey);
+    merge_option_with_args_and_env!(pitch_shift_hotkey);
+    merge_option_with_args_and_env!(robot_hotkey);
+    merge_option_with_args_and_env!(reverb_hotkey);
+    merge_option_with_args_and_env!(echo_hotkey);
     merge_option_with_args_and_env!(http_socket_addr);
     merge_option_with_args_and_env!(telegram_token);
     merge_option_with_args_and_env!(spotify_user);
     merge_option_with_args_and_env!(spotify_pass);
 
+    merge_map_option_with_args_and_env(
+        &mut config.loopback_devices,
+        &arguments,
+        "loopback-devices",
+    )?;
+
     macro_rules! merge_bool_option_with_args_and_env {
         ($name:ident) => {
             merge_bool_option_with_args_and_env(
@@ -301,6 +560,13 @@ fn load_and_merge_app_config() -> Result<AppConfig> {
     merge_bool_option_with_args_and_env!(embed_web);
     merge_bool_option_with_args_and_env!(stream_input_to_loop);
     merge_bool_option_with_args_and_env!(simultaneous_playback);
+    merge_bool_option_with_args_and_env!(loudness_normalization);
+    merge_bool_option_with_args_and_env!(queue_playback);
+    merge_bool_option_with_args_and_env!(mic_ducking);
+    merge_bool_option_with_args_and_env!(noise_gate);
+    merge_bool_option_with_args_and_env!(high_pass);
+    merge_bool_option_with_args_and_env!(compressor);
+    merge_bool_option_with_args_and_env!(watch_soundboards);
 
     merge_flag_with_args_and_env(
         &mut config.print_possible_devices,
@@ -55,6 +55,41 @@ pub(super) fn merge_option_with_args_and_env<T: From<String>>(
     }
 }
 
+/// Merges a map option specified as `name=value;name=value`
+pub(super) fn merge_map_option_with_args_and_env(
+    config_option: &mut Option<std::collections::BTreeMap<String, String>>,
+    args: &clap::ArgMatches,
+    name: &str,
+) -> Result<()> {
+    let value = if args.occurrences_of(name) > 0 {
+        args.value_of(name).unwrap().to_owned()
+    } else if let Ok(value) = std::env::var(get_env_name_from_cli_name(name)) {
+        value
+    } else {
+        return Ok(());
+    };
+
+    let mut map = std::collections::BTreeMap::new();
+    for entry in value.split(';').filter(|entry| !entry.trim().is_empty()) {
+        let mut parts = entry.splitn(2, '=');
+        match (parts.next(), parts.next()) {
+            (Some(key), Some(value)) => {
+                map.insert(key.trim().to_owned(), value.trim().to_owned());
+            }
+            _ => {
+                return Err(anyhow!(
+                    "Unsupported value for map option {} = {}",
+                    name,
+                    entry
+                ))
+            }
+        }
+    }
+    *config_option = Some(map);
+
+    Ok(())
+}
+
 pub(super) fn merge_bool_option_with_args_and_env(
     config_option: &mut Option<bool>,
     args: &clap::ArgMatches,
@@ -176,7 +176,7 @@ fn resolve_local_sound_path(sound: &soundboards::Sound, sound_path: PathBuf) ->
     Ok(new_path)
 }
 
-fn get_file_path_from_hash<T: std::hash::Hash>(t: &T) -> PathBuf {
+pub fn get_file_path_from_hash<T: std::hash::Hash>(t: &T) -> PathBuf {
     let string_hash = utils::calculate_hash(t).to_string();
     let mut file_path = std::env::temp_dir();
     file_path.push(string_hash);
@@ -4,9 +4,9 @@ use super::sound;
 use super::soundboards;
 use anyhow::{anyhow, Context, Result};
 use iced::{
-    button, executor, futures, keyboard, pane_grid, scrollable, slider, Align, Application, Button,
-    Column, Command, Container, Element, Length, PaneGrid, ProgressBar, Row, Scrollable, Settings,
-    Slider, Space, Subscription, Text, VerticalAlignment,
+    button, executor, futures, keyboard, pane_grid, pick_list, scrollable, slider, Align,
+    Application, Button, Column, Command, Container, Element, Length, PaneGrid, PickList,
+    ProgressBar, Row, Scrollable, Settings, Slider, Space, Subscription, Text, VerticalAlignment,
 };
 use log::{error, info, trace, warn};
 use std::fmt;
@@ -47,8 +47,16 @@ pub struct Soundboard {
     stop_button_state: button::State,
     reload_button_state: button::State,
     toggle_layout_button_state: button::State,
-    volume_slider_state: slider::State,
-    current_volume: f32,
+    output_volume_slider_state: slider::State,
+    loopback_volume_slider_state: slider::State,
+    input_volume_slider_state: slider::State,
+    current_volumes: sound::Volumes,
+    input_device_state: pick_list::State<DeviceChoice>,
+    output_device_state: pick_list::State<DeviceChoice>,
+    loopback_device_state: pick_list::State<DeviceChoice>,
+    input_devices: Vec<DeviceChoice>,
+    output_devices: Vec<DeviceChoice>,
+    device_status: sound::DeviceStatus,
     current_style: LayoutStyle,
     soundboard_button_states: Vec<SoundboardButton>,
     hotkey_manager: hotkey::HotkeyManager,
@@ -61,16 +69,91 @@ pub enum SoundboardMessage {
     PlaySound(soundboards::SoundId),
     StopSound(soundboards::SoundId),
     StopAllSound,
-    VolumeChanged(f32),
+    VolumeChanged(sound::VolumeTarget, f32),
+    SwitchDevices(sound::DeviceNames),
     HandlePanelViewMessage(panel_view::PanelViewMessage),
     HandleListViewMessage(list_view::ListViewMessage),
     ToggleLayout,
     ShowSoundboard(String),
     LoadedData(Result<(), String>),
     ReloadData,
+    Undo,
+    Redo,
     Tick,
 }
 
+fn volume_slider<'a>(
+    state: &'a mut slider::State,
+    label: &str,
+    target: sound::VolumeTarget,
+    volume: f32,
+) -> Element<'a, SoundboardMessage> {
+    Row::new()
+        .spacing(5)
+        .align_items(Align::Center)
+        .push(Text::new(label).size(14).width(Length::Units(60)))
+        .push(
+            Slider::new(state, 0.0..=1.0, volume, move |volume| {
+                SoundboardMessage::VolumeChanged(target, volume)
+            })
+            .width(Length::Fill),
+        )
+        .into()
+}
+
+/// Entry of the device pick lists, `None` selects the default device of the backend
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct DeviceChoice(Option<String>);
+
+impl fmt::Display for DeviceChoice {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.0.as_deref().unwrap_or("default"))
+    }
+}
+
+fn device_pick_list<'a>(
+    state: &'a mut pick_list::State<DeviceChoice>,
+    label: &str,
+    devices: &'a [DeviceChoice],
+    selected: Option<String>,
+    on_selected: impl Fn(DeviceChoice) -> SoundboardMessage + 'static,
+) -> Element<'a, SoundboardMessage> {
+    Row::new()
+        .spacing(5)
+        .align_items(Align::Center)
+        .push(Text::new(label).size(14).width(Length::Units(60)))
+        .push(
+            PickList::new(state, devices, Some(DeviceChoice(selected)), on_selected)
+                .text_size(14)
+                .width(Length::Fill),
+        )
+        .into()
+}
+
+impl Soundboard {
+    fn load_devices(&mut self) {
+        let devices = match sound::list_devices() {
+            Ok(devices) => devices,
+            Err(err) => {
+                error!("failed to list devices {:#}", err);
+                return;
+            }
+        };
+        let choices = |device_type: sound::AudioDeviceType| -> Vec<DeviceChoice> {
+            std::iter::once(DeviceChoice(None))
+                .chain(
+                    devices
+                        .iter()
+                        .filter(|d| d.device_type == device_type)
+                        .map(|d| DeviceChoice(Some(d.name.clone()))),
+                )
+                .collect()
+        };
+        self.input_devices = choices(sound::AudioDeviceType::Input);
+        self.output_devices = choices(sound::AudioDeviceType::Output);
+    }
+}
+
 async fn load_config() -> Result<(), String> {
     soundboards::reload_soundboards_from_disk()
         .map_err(|e| format!("reload from disk error: {}", e))?;
@@ -93,8 +176,16 @@ impl Application for Soundboard {
             stop_button_state: button::State::new(),
             reload_button_state: button::State::new(),
             toggle_layout_button_state: button::State::new(),
-            volume_slider_state: slider::State::new(),
-            current_volume: 1.0,
+            output_volume_slider_state: slider::State::new(),
+            loopback_volume_slider_state: slider::State::new(),
+            input_volume_slider_state: slider::State::new(),
+            current_volumes: sound::Volumes::default(),
+            input_device_state: pick_list::State::default(),
+            output_device_state: pick_list::State::default(),
+            loopback_device_state: pick_list::State::default(),
+            input_devices: Vec::new(),
+            output_devices: Vec::new(),
+            device_status: sound::DeviceStatus::default(),
             panel_view: panel_view::PanelView::new(&Vec::new()),
             list_view: list_view::ListView::new(&Vec::new()),
             current_style: LayoutStyle::PanelView,
@@ -116,20 +207,21 @@ impl Application for Soundboard {
         match message {
             SoundboardMessage::Tick => {
                 self.sound_sender
-                    .send(sound::Message::PlayStatus(Vec::new(), 0.0))
+                    .send(sound::Message::PlayStatus(Vec::new(), Default::default()))
                     .expect("sound channel error");
-                if let Some(sound::Message::PlayStatus(sounds, volume)) =
+                if let Some(sound::Message::PlayStatus(sounds, volumes)) =
                     self.sound_receiver.try_iter().last()
                 {
                     self.list_view.active_sounds = sounds.clone();
                     self.panel_view.active_sounds = sounds;
-                    self.current_volume = volume;
+                    self.current_volumes = volumes;
                 }
+                self.device_status = sound::get_device_status();
             }
             SoundboardMessage::PlaySound(sound_id) => {
                 if let Err(err) = self.sound_sender.send(sound::Message::PlaySound(
                     sound_id,
-                    sound::SoundDevices::Both,
+                    soundboards::find_sound_devices(sound_id),
                 )) {
                     error!("failed to play sound {}", err);
                 };
@@ -144,15 +236,24 @@ impl Application for Soundboard {
                     error!("failed to stop all sound {}", err);
                 };
             }
-            SoundboardMessage::VolumeChanged(new_volume) => {
-                self.current_volume = new_volume;
+            SoundboardMessage::VolumeChanged(target, new_volume) => {
+                self.current_volumes.set(target, new_volume);
                 if let Err(err) = self
                     .sound_sender
-                    .send(sound::Message::SetVolume(self.current_volume))
+                    .send(sound::Message::SetVolume(target, new_volume))
                 {
                     error!("failed to set volume {}", err);
                 };
             }
+            SoundboardMessage::SwitchDevices(devices) => {
+                self.device_status.devices = devices.clone();
+                if let Err(err) = self
+                    .sound_sender
+                    .send(sound::Message::SwitchDevices(devices))
+                {
+                    error!("failed to switch devices {}", err);
+                };
+            }
             SoundboardMessage::ToggleLayout => {
                 self.current_style = {
                     if self.current_style == LayoutStyle::ListView {
@@ -180,6 +281,7 @@ impl Application for Soundboard {
                         },
                     );
                     soundboard_buttons[0].selected = true;
+                    self.load_devices();
                     self.soundboard_button_states = soundboard_buttons;
                     self.update(SoundboardMessage::ShowSoundboard(
                         soundboards::get_soundboards()
@@ -240,7 +342,7 @@ impl Application for Soundboard {
                     let _result = self.hotkey_manager.register(hotkey.clone(), move || {
                         if let Err(err) = tx_clone.send(sound::Message::PlaySound(
                             *sound.get_id(),
-                            sound::SoundDevices::Both,
+                            soundboards::find_sound_devices(*sound.get_id()),
                         )) {
                             error!("failed to play sound {}", err);
                         };
@@ -264,6 +366,38 @@ impl Application for Soundboard {
                     self.panel_view.update(panel_view_message);
                 }
             }
+            SoundboardMessage::Undo | SoundboardMessage::Redo => {
+                let selected = match self
+                    .soundboard_button_states
+                    .iter_mut()
+                    .find(|b| b.selected)
+                {
+                    Some(button) => button,
+                    None => return Command::none(),
+                };
+                let id = match soundboards::get_soundboards()
+                    .values()
+                    .find(|s| s.get_name() == selected.name)
+                {
+                    Some(soundboard) => *soundboard.get_id(),
+                    None => return Command::none(),
+                };
+                let result = if matches!(message, SoundboardMessage::Undo) {
+                    soundboards::undo_soundboard(id)
+                } else {
+                    soundboards::redo_soundboard(id)
+                };
+                if let Err(err) = result {
+                    warn!("failed to restore soundboard {:#}", err);
+                    return Command::none();
+                }
+                // the edit may have renamed the soundboard
+                if let Some(soundboard) = soundboards::get_soundboard(id) {
+                    selected.name = soundboard.get_name().to_string();
+                    let name = selected.name.clone();
+                    self.update(SoundboardMessage::ShowSoundboard(name));
+                }
+            }
             #[allow(irrefutable_let_patterns)]
             SoundboardMessage::HandleListViewMessage(list_view_message) => {
                 if let list_view::ListViewMessage::PlaySound(path) = list_view_message {
@@ -278,7 +412,10 @@ impl Application for Soundboard {
     }
 
     fn subscription(&self) -> Subscription<SoundboardMessage> {
-        every(Duration::from_millis(10)).map(|_| SoundboardMessage::Tick)
+        Subscription::batch(vec![
+            every(Duration::from_millis(10)).map(|_| SoundboardMessage::Tick),
+            iced_native::subscription::events_with(history_shortcut),
+        ])
     }
 
     fn view(&mut self) -> Element<SoundboardMessage> {
@@ -412,13 +549,76 @@ impl Application for Soundboard {
             )
             .push(soundboard_row)
             .push(
-                Slider::new(
-                    &mut self.volume_slider_state,
-                    0.0..=1.0,
-                    self.current_volume,
-                    SoundboardMessage::VolumeChanged,
-                )
-                .width(Length::FillPortion(2)),
+                Column::new()
+                    .width(Length::FillPortion(2))
+                    .push(volume_slider(
+                        &mut self.output_volume_slider_state,
+                        "output",
+                        sound::VolumeTarget::Output,
+                        self.current_volumes.output,
+                    ))
+                    .push(volume_slider(
+                        &mut self.loopback_volume_slider_state,
+                        "loopback",
+                        sound::VolumeTarget::Loopback,
+                        self.current_volumes.loopback,
+                    ))
+                    .push(volume_slider(
+                        &mut self.input_volume_slider_state,
+                        "input",
+                        sound::VolumeTarget::Input,
+                        self.current_volumes.input,
+                    )),
+            )
+            .push(
+                Column::new()
+                    .width(Length::FillPortion(2))
+                    .push(device_pick_list(
+                        &mut self.input_device_state,
+                        "input",
+                        &self.input_devices,
+                        self.device_status.devices.input.clone(),
+                        {
+                            let devices = self.device_status.devices.clone();
+                            move |choice| {
+                                SoundboardMessage::SwitchDevices(sound::DeviceNames {
+                                    input: choice.0,
+                                    ..devices.clone()
+                                })
+                            }
+                        },
+                    ))
+                    .push(device_pick_list(
+                        &mut self.output_device_state,
+                        "output",
+                        &self.output_devices,
+                        self.device_status.devices.output.clone(),
+                        {
+                            let devices = self.device_status.devices.clone();
+                            move |choice| {
+                                SoundboardMessage::SwitchDevices(sound::DeviceNames {
+                                    output: choice.0,
+                                    ..devices.clone()
+                                })
+                            }
+                        },
+                    ))
+                    .push(device_pick_list(
+                        &mut self.loopback_device_state,
+                        "loopback",
+                        // the loop device has no default device
+                        &self.output_devices[self.output_devices.len().min(1)..],
+                        self.device_status.devices.loopback.clone(),
+                        {
+                            let devices = self.device_status.devices.clone();
+                            move |choice| {
+                                SoundboardMessage::SwitchDevices(sound::DeviceNames {
+                                    loopback: choice.0,
+                                    ..devices.clone()
+                                })
+                            }
+                        },
+                    )),
             );
 
         let sound_view = {
@@ -448,10 +648,34 @@ impl Application for Soundboard {
             }
         };
 
-        let content = Column::new()
-            .width(Length::Fill)
-            .height(Length::Fill)
-            .spacing(10)
+        let device_warning = {
+            let status = &self.device_status;
+            if let Some(error) = status.error.as_ref() {
+                Some(format!("Audio devices failed: {}", error))
+            } else if !status.missing.is_empty() {
+                Some(format!("Missing devices: {}", status.missing.join(", ")))
+            } else if status.monitor_only {
+                Some("No loop device, sounds are only played on the output device".to_owned())
+            } else {
+                None
+            }
+        };
+
+        let content = device_warning
+            .into_iter()
+            .fold(
+                Column::new()
+                    .width(Length::Fill)
+                    .height(Length::Fill)
+                    .spacing(10),
+                |column, warning| {
+                    column.push(
+                        Text::new(warning)
+                            .size(16)
+                            .color(iced::Color::from_rgb(0.8, 0.4, 0.0)),
+                    )
+                },
+            )
             .push(sound_view)
             .push(Space::with_height(Length::Units(5)))
             .push(bottom_row);
@@ -464,6 +688,28 @@ impl Application for Soundboard {
     }
 }
 
+/// CTRL-Z undoes the last edit of the shown soundboard, CTRL-Y or CTRL-SHIFT-Z redoes it
+fn history_shortcut(
+    event: iced_native::Event,
+    status: iced_native::event::Status,
+) -> Option<SoundboardMessage> {
+    if status == iced_native::event::Status::Captured {
+        return None;
+    }
+    match event {
+        iced_native::Event::Keyboard(keyboard::Event::KeyPressed {
+            key_code,
+            modifiers,
+        }) if modifiers.control => match key_code {
+            keyboard::KeyCode::Z if modifiers.shift => Some(SoundboardMessage::Redo),
+            keyboard::KeyCode::Z => Some(SoundboardMessage::Undo),
+            keyboard::KeyCode::Y => Some(SoundboardMessage::Redo),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
 pub fn every(duration: std::time::Duration) -> iced::Subscription<std::time::Instant> {
     iced::Subscription::from_recipe(Every(duration))
 }
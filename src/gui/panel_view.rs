@@ -1,7 +1,7 @@
 use iced::{
     button, executor, keyboard, pane_grid, scrollable, slider, Align, Application, Button, Column,
-    Command, Container, Element, Length, PaneGrid, ProgressBar, Row, Scrollable, Settings, Slider,
-    Space, Subscription, Text, VerticalAlignment,
+    Command, Container, Element, Image, Length, PaneGrid, ProgressBar, Row, Scrollable, Settings,
+    Slider, Space, Subscription, Text, VerticalAlignment,
 };
 
 use super::sound;
@@ -99,7 +99,7 @@ impl PanelView {
         self.panes.iter_mut().for_each(|(_, state)| {
             if let Some(sound) = sounds
                 .iter()
-                .find(|(_, s, _, _)| s == state.sound_button.sound.get_id())
+                .find(|(_, s, _, _, _)| s == state.sound_button.sound.get_id())
             {
                 state.playing = true;
                 state.status = sound.0;
@@ -132,6 +132,7 @@ struct PanelButtonView {
     sound_button: SoundButton,
     stop_button_state: button::State,
     background_color: iced::Color,
+    icon: Option<iced::image::Handle>,
     pub playing: bool,
     pub status: sound::SoundStatus,
     pub play_duration: std::time::Duration,
@@ -143,13 +144,16 @@ impl PanelButtonView {
         //let random_color= RandomColor::new()
         //.luminosity(Luminosity::Light).to_rgb_array();
         PanelButtonView {
-            sound_button,
             play_duration: std::time::Duration::new(0, 0),
             total_duration: std::time::Duration::new(0, 0),
             stop_button_state: button::State::new(),
-            background_color: iced::Color::from_rgb(0.2, 0.8, 0.2),
+            background_color: sound_color(&sound_button.sound)
+                .unwrap_or_else(|| iced::Color::from_rgb(0.2, 0.8, 0.2)),
+            icon: soundboards::find_sound_icon_path(*sound_button.sound.get_id())
+                .map(iced::image::Handle::from_path),
             playing: false, //iced::Color::from_rgb((random_color[0] as f32) / 255.0, (random_color[1] as f32) / 255.0, (random_color[2] as f32) / 255.0)
             status: sound::SoundStatus::Downloading,
+            sound_button,
         }
     }
     fn view(&mut self, _pane: pane_grid::Pane) -> Element<PanelViewMessage> {
@@ -160,12 +164,20 @@ impl PanelButtonView {
                 String::new()
             }
         };
-        let column = Column::new()
+        let name_text = match sound_emoji(&self.sound_button.sound) {
+            Some(emoji) => format!("{} {}", emoji, self.sound_button.sound.get_name()),
+            None => self.sound_button.sound.get_name().to_string(),
+        };
+        let mut column = Column::new()
             .spacing(5)
             .align_items(Align::Center)
-            .width(Length::Fill)
+            .width(Length::Fill);
+        if let Some(icon) = &self.icon {
+            column = column.push(Image::new(icon.clone()).height(Length::Units(32)));
+        }
+        column = column
             .push(
-                Text::new(self.sound_button.sound.get_name())
+                Text::new(name_text)
                     .size(18)
                     .vertical_alignment(VerticalAlignment::Center),
             )
@@ -174,6 +186,13 @@ impl PanelButtonView {
                     .size(14)
                     .vertical_alignment(VerticalAlignment::Center),
             );
+        if let Some(description) = self.sound_button.sound.get_description() {
+            column = column.push(
+                Text::new(description)
+                    .size(12)
+                    .vertical_alignment(VerticalAlignment::Center),
+            );
+        }
 
         let cont = Container::new(column)
             .width(Length::Fill)
@@ -212,7 +231,7 @@ impl PanelButtonView {
                 .width(Length::Fill)
                 .height(Length::Fill)
                 .push(button_play);
-            if self.status == sound::SoundStatus::Playing {
+            if self.status != sound::SoundStatus::Downloading {
                 column = column.push(progress_bar);
             } else {
                 column = column.push(
@@ -237,3 +256,21 @@ impl PanelButtonView {
         }
     }
 }
+
+/// Returns the color of the sound, invalid colors are ignored
+fn sound_color(sound: &soundboards::Sound) -> Option<iced::Color> {
+    let (r, g, b) = soundboards::parse_color(sound.get_color().as_ref()?).ok()?;
+    Some(iced::Color::from_rgb(
+        r as f32 / 255.0,
+        g as f32 / 255.0,
+        b as f32 / 255.0,
+    ))
+}
+
+/// Returns the icon of the sound if it is an emoji
+fn sound_emoji(sound: &soundboards::Sound) -> Option<&str> {
+    sound
+        .get_icon()
+        .as_deref()
+        .filter(|icon| !soundboards::is_image_icon(icon))
+}
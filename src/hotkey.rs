@@ -15,8 +15,8 @@ use std::sync::Arc;
 use thiserror::Error;
 
 type GlobalListener = Lazy<Arc<Mutex<Listener>>>;
-type GlobalHotkeyMap =
-    Arc<Mutex<HashMap<Hotkey, HashMap<usize, Box<dyn 'static + FnMut() + Send>>>>>;
+type HotkeyCallback = Box<dyn 'static + FnMut(bool) + Send>;
+type GlobalHotkeyMap = Arc<Mutex<HashMap<Hotkey, HashMap<usize, HotkeyCallback>>>>;
 
 static GLOBAL_LISTENER: GlobalListener = Lazy::new(|| Arc::new(Mutex::new(Listener::new())));
 static GLOBAL_HOTKEY_MAP: Lazy<GlobalHotkeyMap> = Lazy::new(GlobalHotkeyMap::default);
@@ -44,9 +44,24 @@ impl HotkeyManager {
             id: ID_COUNTER.fetch_add(1, Ordering::Relaxed),
         }
     }
-    pub fn register<F>(&mut self, hotkey: Hotkey, callback: F) -> Result<(), HotkeyManagerError>
+    pub fn register<F>(&mut self, hotkey: Hotkey, mut callback: F) -> Result<(), HotkeyManagerError>
     where
         F: 'static + FnMut() + Send,
+    {
+        self.register_with_release(hotkey, move |pressed| {
+            if pressed {
+                callback();
+            }
+        })
+    }
+    /// The callback is called with true when the hotkey is pressed and with false when it is released
+    pub fn register_with_release<F>(
+        &mut self,
+        hotkey: Hotkey,
+        callback: F,
+    ) -> Result<(), HotkeyManagerError>
+    where
+        F: 'static + FnMut(bool) + Send,
     {
         let position = self.registered_hotkeys.iter().position(|h| h == &hotkey);
         if position.is_some() {
@@ -63,18 +78,18 @@ impl HotkeyManager {
                 let hotkey_clone = hotkey.clone();
                 GLOBAL_LISTENER
                     .lock()
-                    .register_hotkey(
+                    .register_hotkey_with_release(
                         ListenerHotkey::new(hotkey.modifier_as_flag(), hotkey.key as u32),
-                        move || {
+                        move |pressed| {
                             if let Some(entry) = GLOBAL_HOTKEY_MAP.lock().get_mut(&hotkey) {
                                 for (_, cb) in entry.iter_mut() {
-                                    cb();
+                                    cb(pressed);
                                 }
                             }
                         },
                     )
                     .with_context(|| format!("Failed to register hotkey {}", hotkey_clone))?;
-                let mut new_map: HashMap<usize, Box<dyn 'static + FnMut() + Send>> = HashMap::new();
+                let mut new_map: HashMap<usize, HotkeyCallback> = HashMap::new();
                 new_map.insert(self.id, Box::new(callback));
                 entry.insert(new_map);
             }
@@ -29,12 +29,51 @@ struct HotkeyFireEvent {
 
 #[derive(Debug, Deserialize, Clone, Serialize)]
 struct SoundPlayRequest {
-    devices: sound::SoundDevices,
+    /// Uses the routing of the sound or soundboard if not specified
+    devices: Option<sound::SoundDevices>,
 }
 
 #[derive(Debug, Deserialize, Clone, Serialize, Default)]
 struct VolumeRequest {
     volume: f32,
+    /// Sets the output and loopback volume if not specified
+    target: Option<sound::VolumeTarget>,
+}
+
+#[derive(Debug, Deserialize, Clone, Serialize, Default)]
+struct SeekRequest {
+    /// Position in seconds from the start of the sound
+    position: f32,
+}
+
+#[derive(Debug, Deserialize, Clone, Serialize)]
+struct QueueAddRequest {
+    sound_id: Ulid,
+    /// Uses the routing of the sound or soundboard if not specified
+    devices: Option<sound::SoundDevices>,
+}
+
+#[derive(Debug, Deserialize, Clone, Serialize, Default)]
+struct QueueMoveRequest {
+    from: usize,
+    to: usize,
+}
+
+#[derive(Debug, Deserialize, Clone, Serialize)]
+struct InputProcessorInfo {
+    processor: sound::InputProcessor,
+    enabled: bool,
+}
+
+#[derive(Debug, Deserialize, Clone, Serialize)]
+struct VoiceEffectRequest {
+    effect: Option<sound::VoiceEffect>,
+}
+
+#[derive(Debug, Deserialize, Clone, Serialize)]
+struct VoiceEffectsInfo {
+    effect: Option<sound::VoiceEffect>,
+    effects: Vec<sound::VoiceEffect>,
 }
 
 #[derive(Debug, Deserialize, Clone, Serialize, Default)]
@@ -42,6 +81,7 @@ struct SoundboardChangeRequest {
     name: String,
     hotkey: Option<String>,
     position: Option<usize>,
+    devices: Option<sound::SoundDevices>,
 }
 
 #[derive(Debug, Deserialize, Clone, Serialize)]
@@ -49,6 +89,19 @@ struct SoundChangeRequest {
     name: String,
     hotkey: Option<String>,
     source: soundboards::Source,
+    volume: Option<f32>,
+    fade_in: Option<f32>,
+    fade_out: Option<f32>,
+    #[serde(rename = "loop")]
+    loop_mode: Option<soundboards::LoopMode>,
+    pitch: Option<f32>,
+    speed: Option<f32>,
+    reverse: Option<bool>,
+    devices: Option<sound::SoundDevices>,
+    tags: Option<Vec<String>>,
+    description: Option<String>,
+    color: Option<String>,
+    icon: Option<String>,
 }
 
 #[derive(Debug, Deserialize, Clone, Serialize)]
@@ -69,6 +122,7 @@ struct StrippedSoundboardInfo {
     name: String,
     hotkey: Option<String>,
     position: Option<usize>,
+    devices: Option<sound::SoundDevices>,
     id: Ulid,
 }
 
@@ -78,6 +132,7 @@ impl StrippedSoundboardInfo {
             name: soundboard.get_name().to_string(),
             hotkey: soundboard.get_hotkey_string_or_none(),
             position: *soundboard.get_position(),
+            devices: soundboard.get_devices().clone(),
             id: *soundboard.get_id(),
         }
     }
@@ -88,6 +143,7 @@ struct ExtendedSoundboardInfo {
     name: String,
     hotkey: Option<String>,
     position: Option<usize>,
+    devices: Option<sound::SoundDevices>,
     id: Ulid,
     sounds: Vec<StrippedSoundInfo>,
 }
@@ -97,6 +153,19 @@ struct StrippedSoundInfo {
     name: String,
     hotkey: Option<String>,
     source: soundboards::Source,
+    volume: Option<f32>,
+    fade_in: Option<f32>,
+    fade_out: Option<f32>,
+    #[serde(rename = "loop")]
+    loop_mode: Option<soundboards::LoopMode>,
+    pitch: Option<f32>,
+    speed: Option<f32>,
+    reverse: Option<bool>,
+    devices: Option<sound::SoundDevices>,
+    tags: Option<Vec<String>>,
+    description: Option<String>,
+    color: Option<String>,
+    icon: Option<String>,
     id: Ulid,
 }
 
@@ -106,6 +175,18 @@ impl StrippedSoundInfo {
             name: sound.get_name().to_string(),
             hotkey: sound.get_hotkey_string_or_none(),
             source: sound.get_source().clone(),
+            volume: sound.get_volume(),
+            fade_in: sound.get_fade_in(),
+            fade_out: sound.get_fade_out(),
+            loop_mode: sound.get_loop_mode(),
+            pitch: sound.get_pitch(),
+            speed: sound.get_speed(),
+            reverse: sound.get_reverse(),
+            devices: sound.get_devices().clone(),
+            tags: sound.get_tags().clone(),
+            description: sound.get_description().clone(),
+            color: sound.get_color().clone(),
+            icon: sound.get_icon().clone(),
             id: *sound.get_id(),
         }
     }
@@ -113,8 +194,32 @@ impl StrippedSoundInfo {
 
 #[derive(Debug, Deserialize, Clone, Serialize, Default)]
 struct PlayStatusResponse {
-    volume: f32,
+    volumes: sound::Volumes,
     sounds: Vec<StrippedSoundActiveInfo>,
+    queue: Vec<StrippedQueueInfo>,
+}
+
+#[derive(Debug, Deserialize, Clone, Serialize)]
+struct StrippedQueueInfo {
+    devices: sound::SoundDevices,
+    sounds: Vec<StrippedSoundInfo>,
+}
+
+fn get_queue_info() -> Vec<StrippedQueueInfo> {
+    let mut queue_info: Vec<StrippedQueueInfo> = sound::get_queue()
+        .into_iter()
+        .filter(|(_, queue)| !queue.is_empty())
+        .map(|(devices, queue)| StrippedQueueInfo {
+            devices,
+            sounds: queue
+                .into_iter()
+                .filter_map(soundboards::find_sound)
+                .map(|sound| StrippedSoundInfo::from(&sound))
+                .collect(),
+        })
+        .collect();
+    queue_info.sort_by(|a, b| a.devices.cmp(&b.devices));
+    queue_info
 }
 
 #[derive(Debug, Deserialize, Clone, Serialize)]
@@ -124,6 +229,7 @@ struct StrippedSoundActiveInfo {
     id: soundboards::SoundId,
     total_duration: f32,
     play_duration: f32,
+    loop_count: u32,
 }
 
 #[derive(Debug, Deserialize, Clone, Serialize, Default)]
@@ -161,6 +267,30 @@ impl ResultErrors {
     }
 }
 
+/// Formats errors of soundboard saves, merge conflicts list the conflicting settings and sounds
+fn format_save_error(err: anyhow::Error) -> warp::reply::WithStatus<warp::reply::Json> {
+    let conflict = match err.downcast_ref::<soundboards::MergeConflict>() {
+        Some(conflict) => conflict,
+        None => return format_json_error(err),
+    };
+    let fields = conflict.fields.iter().map(|field| ErrorObject {
+        code: "409".to_string(),
+        title: "Conflict".to_string(),
+        detail: format!("soundboard setting {} changed on disk", field),
+    });
+    let sounds = conflict.sounds.iter().map(|(id, name)| ErrorObject {
+        code: "409".to_string(),
+        title: "Conflict".to_string(),
+        detail: format!("sound {} ({}) changed on disk", name, id),
+    });
+    warp::reply::with_status(
+        warp::reply::json(&ResultErrors {
+            errors: fields.chain(sounds).collect(),
+        }),
+        warp::http::StatusCode::CONFLICT,
+    )
+}
+
 fn format_json_error<T: std::fmt::Display>(err: T) -> warp::reply::WithStatus<warp::reply::Json> {
     warp::reply::with_status(
         warp::reply::json(&ResultErrors::with_error(
@@ -310,13 +440,9 @@ pub async fn run(
                     hotkey: soundboard.get_hotkey_string_or_none(),
                     id,
                     position: *soundboard.get_position(),
+                    devices: soundboard.get_devices().clone(),
                     sounds: soundboard.iter().fold(Vec::new(), |mut v, a| {
-                        v.push(StrippedSoundInfo {
-                            name: a.get_name().to_string(),
-                            hotkey: a.get_hotkey_string_or_none(),
-                            id: *a.get_id(),
-                            source: a.get_source().clone(),
-                        });
+                        v.push(StrippedSoundInfo::from(a));
                         v
                     }),
                 })),
@@ -342,9 +468,10 @@ pub async fn run(
                 soundboard.set_name(&soundboard_change_request.name);
 
                 soundboard.set_position(soundboard_change_request.position);
+                soundboard.set_devices(soundboard_change_request.devices);
 
                 if let Err(err) = soundboards::update_soundboards(soundboard) {
-                    return format_json_error(err);
+                    return format_save_error(err);
                 }
                 let soundboard = soundboards::get_soundboard(soundboard_id).unwrap();
                 warp::reply::with_status(
@@ -356,6 +483,38 @@ pub async fn run(
             },
         );
 
+    let soundboards_soundboard_undo_route = check_soundboard_id()
+        .and(warp::path!("undo"))
+        .and(warp::post())
+        .map(|(_, soundboard_id): (soundboards::Soundboard, Ulid)| {
+            if let Err(err) = soundboards::undo_soundboard(soundboard_id) {
+                return format_save_error(err);
+            }
+            let soundboard = soundboards::get_soundboard(soundboard_id).unwrap();
+            warp::reply::with_status(
+                warp::reply::json(&ResultData::with_data(StrippedSoundboardInfo::from(
+                    &soundboard,
+                ))),
+                warp::http::StatusCode::OK,
+            )
+        });
+
+    let soundboards_soundboard_redo_route = check_soundboard_id()
+        .and(warp::path!("redo"))
+        .and(warp::post())
+        .map(|(_, soundboard_id): (soundboards::Soundboard, Ulid)| {
+            if let Err(err) = soundboards::redo_soundboard(soundboard_id) {
+                return format_save_error(err);
+            }
+            let soundboard = soundboards::get_soundboard(soundboard_id).unwrap();
+            warp::reply::with_status(
+                warp::reply::json(&ResultData::with_data(StrippedSoundboardInfo::from(
+                    &soundboard,
+                ))),
+                warp::http::StatusCode::OK,
+            )
+        });
+
     type AddSoundMultipartResult = (
         (soundboards::Soundboard, Ulid, soundboards::Sound, Ulid),
         Vec<(String, Vec<u8>)>,
@@ -419,7 +578,7 @@ pub async fn run(
                 }
 
                 if let Err(err) = soundboards::update_soundboards(soundboard) {
-                    return format_json_error(err);
+                    return format_save_error(err);
                 }
                 warp::reply::with_status(
                     warp::reply::json(&ResultData::with_data(added_sounds)),
@@ -473,7 +632,7 @@ pub async fn run(
                 }
 
                 if let Err(err) = soundboards::update_soundboards(soundboard) {
-                    return format_json_error(err);
+                    return format_save_error(err);
                 }
                 warp::reply::with_status(
                     warp::reply::json(&ResultData::with_data(StrippedSoundInfo::from(
@@ -529,7 +688,7 @@ pub async fn run(
                 }
 
                 if let Err(err) = soundboards::update_soundboards(soundboard) {
-                    return format_json_error(err);
+                    return format_save_error(err);
                 }
                 warp::reply::with_status(
                     warp::reply::json(&ResultData::with_data(StrippedSoundInfo::from(
@@ -570,7 +729,7 @@ pub async fn run(
                 }
 
                 if let Err(err) = soundboards::update_soundboards(soundboard) {
-                    return format_json_error(err);
+                    return format_save_error(err);
                 }
                 warp::reply::with_status(
                     warp::reply::json(&ResultData::with_data(StrippedSoundInfo::from(&new_sound))),
@@ -596,6 +755,33 @@ pub async fn run(
             },
         );
 
+    let soundboards_sounds_icon_route = check_sound_id()
+        .and(warp::path!("icon"))
+        .and(warp::get())
+        .map(
+            move |(soundboard, _soundboard_id, sound, _sound_id): (
+                soundboards::Soundboard,
+                soundboards::SoundboardId,
+                soundboards::Sound,
+                soundboards::SoundId,
+            )| {
+                let icon = soundboard.get_sound_icon_path(&sound).and_then(|path| {
+                    let path = path.ok_or_else(|| anyhow!("sound has no image icon"))?;
+                    let image = std::fs::read(&path)
+                        .with_context(|| format!("failed to read icon {}", path.display()))?;
+                    Ok((path, image))
+                });
+                match icon {
+                    Ok((path, image)) => {
+                        let mime = mime_guess::from_path(path).first_or_octet_stream();
+                        warp::reply::with_header(image, "content-type", mime.as_ref())
+                            .into_response()
+                    }
+                    Err(err) => format_json_error(err).into_response(),
+                }
+            },
+        );
+
     let soundboards_sounds_change_sound_route = check_sound_id()
         .and(warp::path::end())
         .and(warp::put())
@@ -628,10 +814,38 @@ pub async fn run(
                     if let Err(err) = changed_sound.set_source(change_request.source.clone()) {
                         return format_json_error(err);
                     }
+                    if let Err(err) = changed_sound.set_volume(change_request.volume) {
+                        return format_json_error(err);
+                    }
+                    if let Err(err) = changed_sound.set_fade_in(change_request.fade_in) {
+                        return format_json_error(err);
+                    }
+                    if let Err(err) = changed_sound.set_fade_out(change_request.fade_out) {
+                        return format_json_error(err);
+                    }
+                    if let Err(err) = changed_sound.set_loop_mode(change_request.loop_mode) {
+                        return format_json_error(err);
+                    }
+                    if let Err(err) = changed_sound.set_pitch(change_request.pitch) {
+                        return format_json_error(err);
+                    }
+                    if let Err(err) = changed_sound.set_speed(change_request.speed) {
+                        return format_json_error(err);
+                    }
+                    if let Err(err) = changed_sound.set_color(change_request.color) {
+                        return format_json_error(err);
+                    }
+                    changed_sound.set_reverse(change_request.reverse);
+                    changed_sound.set_devices(change_request.devices);
+                    changed_sound.set_tags(change_request.tags);
+                    changed_sound.set_description(change_request.description);
+                    if let Err(err) = changed_sound.set_icon(change_request.icon) {
+                        return format_json_error(err);
+                    }
                     sound = changed_sound.clone();
                 }
                 if let Err(err) = soundboards::update_soundboards(soundboard) {
-                    return format_json_error(err);
+                    return format_save_error(err);
                 }
                 warp::reply::with_status(
                     warp::reply::json(&ResultData::with_data(StrippedSoundInfo::from(&sound))),
@@ -655,7 +869,7 @@ pub async fn run(
                 }
 
                 if let Err(err) = soundboards::update_soundboards(soundboard) {
-                    return format_json_error(err);
+                    return format_save_error(err);
                 }
 
                 warp::reply::with_status(
@@ -665,21 +879,56 @@ pub async fn run(
             },
         );
 
+    #[cfg(feature = "wav")]
+    let sounds_export_route = check_sound_id()
+        .and(warp::path!("export"))
+        .and(warp::get())
+        .and_then(
+            move |(_soundboard, _soundboard_index, sound, _sound_index): (
+                soundboards::Soundboard,
+                soundboards::SoundboardId,
+                soundboards::Sound,
+                soundboards::SoundId,
+            )| async move {
+                let name = sound.get_name().replace('"', "");
+                // downloading and decoding the sound blocks
+                let export = tokio::task::spawn_blocking(move || {
+                    let mut wav = std::io::Cursor::new(Vec::new());
+                    sound::export_sound_wav(&sound, &mut wav).map(|_| wav.into_inner())
+                })
+                .await;
+                let wav = match export {
+                    Ok(Ok(wav)) => wav,
+                    Ok(Err(err)) => return Ok(format_json_error(err).into_response()),
+                    Err(err) => return Ok(format_json_error(err).into_response()),
+                };
+                let disposition = format!("attachment; filename=\"{}.wav\"", name);
+                let reply = warp::reply::with_header(wav, "content-type", "audio/wav");
+                Ok::<_, Rejection>(
+                    warp::reply::with_header(reply, "content-disposition", disposition)
+                        .into_response(),
+                )
+            },
+        );
+
     let gui_sender_clone = gui_sender.clone();
     let sounds_play_route = check_sound_id()
         .and(warp::path!("play"))
         .and(warp::post())
         .and(warp::body::json())
         .map(
-            move |(_soundboard, _soundboard_index, sound, _): (
+            move |(soundboard, _soundboard_index, sound, _): (
                 soundboards::Soundboard,
                 soundboards::SoundboardId,
                 soundboards::Sound,
                 soundboards::SoundId,
             ),
                   request: SoundPlayRequest| {
+                let devices = request
+                    .devices
+                    .unwrap_or_else(|| soundboard.get_sound_devices(&sound));
                 gui_sender_clone
-                    .send(sound::Message::PlaySound(*sound.get_id(), request.devices))
+                    .send(sound::Message::PlaySound(*sound.get_id(), devices))
                     .unwrap();
                 warp::reply::with_status(
                     warp::reply::json(&ResultData::with_data(format!(
@@ -715,14 +964,101 @@ pub async fn run(
             },
         );
 
+    let gui_sender_clone = gui_sender.clone();
+    let sounds_pause_route = warp::path!("sounds" / Ulid / "pause")
+        .and(warp::post())
+        .map(move |sound_id: soundboards::SoundId| {
+            if soundboards::find_sound(sound_id).is_none() {
+                return format_json_error("no sound with that id");
+            }
+            gui_sender_clone
+                .send(sound::Message::PauseSound(sound_id))
+                .unwrap();
+            warp::reply::with_status(
+                warp::reply::json(&ResultData::with_data("PauseSound".to_string())),
+                warp::http::StatusCode::OK,
+            )
+        });
+
+    let gui_sender_clone = gui_sender.clone();
+    let sounds_resume_route = warp::path!("sounds" / Ulid / "resume")
+        .and(warp::post())
+        .map(move |sound_id: soundboards::SoundId| {
+            if soundboards::find_sound(sound_id).is_none() {
+                return format_json_error("no sound with that id");
+            }
+            gui_sender_clone
+                .send(sound::Message::ResumeSound(sound_id))
+                .unwrap();
+            warp::reply::with_status(
+                warp::reply::json(&ResultData::with_data("ResumeSound".to_string())),
+                warp::http::StatusCode::OK,
+            )
+        });
+
+    let gui_sender_clone = gui_sender.clone();
+    let sounds_seek_route = warp::path!("sounds" / Ulid / "seek")
+        .and(warp::post())
+        .and(warp::body::json())
+        .map(
+            move |sound_id: soundboards::SoundId, request: SeekRequest| {
+                if soundboards::find_sound(sound_id).is_none() {
+                    return format_json_error("no sound with that id");
+                }
+                if !request.position.is_finite() || request.position < 0.0 {
+                    return format_json_error("seek position should be positive");
+                }
+                gui_sender_clone
+                    .send(sound::Message::SeekSound(
+                        sound_id,
+                        std::time::Duration::from_secs_f32(request.position),
+                    ))
+                    .unwrap();
+                warp::reply::with_status(
+                    warp::reply::json(&ResultData::with_data("SeekSound".to_string())),
+                    warp::http::StatusCode::OK,
+                )
+            },
+        );
+
+    let gui_sender_clone = gui_sender.clone();
+    let sounds_pause_all_route =
+        warp::path!("sounds" / "pauseall")
+            .and(warp::post())
+            .map(move || {
+                gui_sender_clone.send(sound::Message::PauseAll).unwrap();
+                warp::reply::with_status(
+                    warp::reply::json(&ResultData::with_data("PauseAllSound".to_string())),
+                    warp::http::StatusCode::OK,
+                )
+            });
+
+    let gui_sender_clone = gui_sender.clone();
+    let sounds_resume_all_route =
+        warp::path!("sounds" / "resumeall")
+            .and(warp::post())
+            .map(move || {
+                gui_sender_clone.send(sound::Message::ResumeAll).unwrap();
+                warp::reply::with_status(
+                    warp::reply::json(&ResultData::with_data("ResumeAllSound".to_string())),
+                    warp::http::StatusCode::OK,
+                )
+            });
+
     let gui_sender_clone = gui_sender.clone();
     let sounds_set_volume = warp::path!("sounds" / "volume")
         .and(warp::post())
         .and(warp::body::json())
         .map(move |volume: VolumeRequest| {
-            gui_sender_clone
-                .send(sound::Message::SetVolume(volume.volume))
-                .unwrap();
+            let targets = match volume.target {
+                Some(target) => vec![target],
+                None => vec![sound::VolumeTarget::Output, sound::VolumeTarget::Loopback],
+            };
+            for target in targets {
+                gui_sender_clone
+                    .send(sound::Message::SetVolume(target, volume.volume))
+                    .unwrap();
+            }
             warp::reply::with_status(
                 warp::reply::json(&ResultData::with_data("SetVolume".to_string())),
                 warp::http::StatusCode::OK,
@@ -741,6 +1077,198 @@ pub async fn run(
                 )
             });
 
+    let queue_route = warp::path!("queue").and(warp::get()).map(|| {
+        warp::reply::with_status(
+            warp::reply::json(&ResultData::with_data(get_queue_info())),
+            warp::http::StatusCode::OK,
+        )
+    });
+
+    let gui_sender_clone = gui_sender.clone();
+    let queue_add_route = warp::path!("queue")
+        .and(warp::post())
+        .and(warp::body::json())
+        .map(move |request: QueueAddRequest| {
+            if soundboards::find_sound(request.sound_id).is_none() {
+                return format_json_error("no sound with that id");
+            }
+            let devices = request
+                .devices
+                .unwrap_or_else(|| soundboards::find_sound_devices(request.sound_id));
+            gui_sender_clone
+                .send(sound::Message::Enqueue(request.sound_id, devices))
+                .unwrap();
+            warp::reply::with_status(
+                warp::reply::json(&ResultData::with_data("Enqueue".to_string())),
+                warp::http::StatusCode::OK,
+            )
+        });
+
+    let gui_sender_clone = gui_sender.clone();
+    let queue_skip_route = warp::path!("queue" / sound::SoundDevices / "skip")
+        .and(warp::post())
+        .map(move |devices: sound::SoundDevices| {
+            gui_sender_clone
+                .send(sound::Message::Skip(devices))
+                .unwrap();
+            warp::reply::with_status(
+                warp::reply::json(&ResultData::with_data("Skip".to_string())),
+                warp::http::StatusCode::OK,
+            )
+        });
+
+    let gui_sender_clone = gui_sender.clone();
+    let queue_clear_route = warp::path!("queue" / sound::SoundDevices)
+        .and(warp::delete())
+        .map(move |devices: sound::SoundDevices| {
+            gui_sender_clone
+                .send(sound::Message::ClearQueue(devices))
+                .unwrap();
+            warp::reply::with_status(
+                warp::reply::json(&ResultData::with_data("ClearQueue".to_string())),
+                warp::http::StatusCode::OK,
+            )
+        });
+
+    let gui_sender_clone = gui_sender.clone();
+    let queue_move_route = warp::path!("queue" / sound::SoundDevices / "move")
+        .and(warp::post())
+        .and(warp::body::json())
+        .map(
+            move |devices: sound::SoundDevices, request: QueueMoveRequest| {
+                let queue_len = sound::get_queue()
+                    .get(&devices)
+                    .map_or(0, |queue| queue.len());
+                if request.from >= queue_len || request.to >= queue_len {
+                    return format_json_error("queue index out of bounds");
+                }
+                gui_sender_clone
+                    .send(sound::Message::MoveInQueue(
+                        devices,
+                        request.from,
+                        request.to,
+                    ))
+                    .unwrap();
+                warp::reply::with_status(
+                    warp::reply::json(&ResultData::with_data("MoveInQueue".to_string())),
+                    warp::http::StatusCode::OK,
+                )
+            },
+        );
+
+    let gui_sender_clone = gui_sender.clone();
+    let queue_remove_route = warp::path!("queue" / sound::SoundDevices / usize)
+        .and(warp::delete())
+        .map(move |devices: sound::SoundDevices, index: usize| {
+            let queue_len = sound::get_queue()
+                .get(&devices)
+                .map_or(0, |queue| queue.len());
+            if index >= queue_len {
+                return format_json_error("queue index out of bounds");
+            }
+            gui_sender_clone
+                .send(sound::Message::RemoveFromQueue(devices, index))
+                .unwrap();
+            warp::reply::with_status(
+                warp::reply::json(&ResultData::with_data("RemoveFromQueue".to_string())),
+                warp::http::StatusCode::OK,
+            )
+        });
+
+    let input_processors_route = warp::path!("input" / "processors")
+        .and(warp::get())
+        .map(|| {
+            let processors: Vec<InputProcessorInfo> = sound::get_input_processors()
+                .into_iter()
+                .map(|(processor, enabled)| InputProcessorInfo { processor, enabled })
+                .collect();
+            warp::reply::with_status(
+                warp::reply::json(&ResultData::with_data(processors)),
+                warp::http::StatusCode::OK,
+            )
+        });
+
+    let gui_sender_clone = gui_sender.clone();
+    let input_processors_set_route = warp::path!("input" / "processors")
+        .and(warp::post())
+        .and(warp::body::json())
+        .map(move |request: InputProcessorInfo| {
+            gui_sender_clone
+                .send(sound::Message::SetInputProcessor(
+                    request.processor,
+                    request.enabled,
+                ))
+                .unwrap();
+            warp::reply::with_status(
+                warp::reply::json(&ResultData::with_data("SetInputProcessor".to_string())),
+                warp::http::StatusCode::OK,
+            )
+        });
+
+    let effects_route = warp::path!("effects").and(warp::get()).map(|| {
+        warp::reply::with_status(
+            warp::reply::json(&ResultData::with_data(VoiceEffectsInfo {
+                effect: sound::get_voice_effect(),
+                effects: sound::VoiceEffect::ALL.to_vec(),
+            })),
+            warp::http::StatusCode::OK,
+        )
+    });
+
+    let gui_sender_clone = gui_sender.clone();
+    let effects_set_route = warp::path!("effects")
+        .and(warp::post())
+        .and(warp::body::json())
+        .map(move |request: VoiceEffectRequest| {
+            gui_sender_clone
+                .send(sound::Message::SetVoiceEffect(request.effect))
+                .unwrap();
+            warp::reply::with_status(
+                warp::reply::json(&ResultData::with_data("SetVoiceEffect".to_string())),
+                warp::http::StatusCode::OK,
+            )
+        });
+
+    let targets_route = warp::path!("targets").and(warp::get()).map(|| {
+        warp::reply::with_status(
+            warp::reply::json(&ResultData::with_data(sound::get_routing_targets())),
+            warp::http::StatusCode::OK,
+        )
+    });
+
+    let devices_route =
+        warp::path!("devices")
+            .and(warp::get())
+            .map(|| match sound::list_devices() {
+                Ok(devices) => warp::reply::with_status(
+                    warp::reply::json(&ResultData::with_data(devices)),
+                    warp::http::StatusCode::OK,
+                ),
+                Err(err) => format_json_error(err),
+            });
+
+    let devices_current_route = warp::path!("devices" / "current").and(warp::get()).map(|| {
+        warp::reply::with_status(
+            warp::reply::json(&ResultData::with_data(sound::get_device_status())),
+            warp::http::StatusCode::OK,
+        )
+    });
+
+    let gui_sender_clone = gui_sender.clone();
+    let devices_switch_route = warp::path!("devices" / "current")
+        .and(warp::post())
+        .and(warp::body::json())
+        .map(move |request: sound::DeviceNames| {
+            // without a loopback device sounds are only played on the output device
+            gui_sender_clone
+                .send(sound::Message::SwitchDevices(request))
+                .unwrap();
+            warp::reply::with_status(
+                warp::reply::json(&ResultData::with_data("SwitchDevices".to_string())),
+                warp::http::StatusCode::OK,
+            )
+        });
+
     fn sse_json(id: PlayStatusResponse) -> Result<impl ServerSentEvent, Infallible> {
         Ok(warp::sse::json(id))
     }
@@ -755,9 +1283,9 @@ pub async fn run(
             let event_stream =
                 tokio::time::interval(tokio::time::Duration::from_millis(111)).map(move |_| loop {
                     gui_sender_clone
-                        .send(sound::Message::PlayStatus(Vec::new(), 0.0))
+                        .send(sound::Message::PlayStatus(Vec::new(), Default::default()))
                         .unwrap();
-                    if let Ok(sound::Message::PlayStatus(sounds, volume)) =
+                    if let Ok(sound::Message::PlayStatus(sounds, volumes)) =
                         gui_receiver_clone.recv()
                     {
                         let mut sound_info: Vec<StrippedSoundActiveInfo> = Vec::new();
@@ -772,12 +1300,14 @@ pub async fn run(
                                         .3
                                         .unwrap_or_else(|| std::time::Duration::from_secs(0))
                                         .as_secs_f32(),
+                                    loop_count: sound.4,
                                 });
                             }
                         }
                         let play_status_response = PlayStatusResponse {
                             sounds: sound_info,
-                            volume,
+                            volumes,
+                            queue: get_queue_info(),
                         };
                         return sse_json(play_status_response);
                     }
@@ -790,10 +1320,10 @@ pub async fn run(
         .and(warp::get())
         .map(move || {
             gui_sender_clone
-                .send(sound::Message::PlayStatus(Vec::new(), 0.0))
+                .send(sound::Message::PlayStatus(Vec::new(), Default::default()))
                 .unwrap();
             match gui_receiver.recv() {
-                Ok(sound::Message::PlayStatus(sounds, volume)) => {
+                Ok(sound::Message::PlayStatus(sounds, volumes)) => {
                     let mut sound_info: Vec<StrippedSoundActiveInfo> = Vec::new();
                     for sound in sounds {
                         let full_sound = soundboards::find_sound(sound.1).unwrap();
@@ -806,11 +1336,13 @@ pub async fn run(
                                 .3
                                 .unwrap_or_else(|| std::time::Duration::from_secs(0))
                                 .as_secs_f32(),
+                            loop_count: sound.4,
                         })
                     }
                     let play_status_response = PlayStatusResponse {
                         sounds: sound_info,
-                        volume,
+                        volumes,
+                        queue: get_queue_info(),
                     };
                     warp::reply::with_status(
                         warp::reply::json(&ResultData::with_data(play_status_response)),
@@ -829,6 +1361,25 @@ pub async fn run(
         Ok(warp::sse::data(id))
     }
 
+    let soundboards_events_route =
+        warp::path!("soundboards" / "events")
+            .and(warp::get())
+            .map(|| {
+                let mut last_version = soundboards::get_soundboards_version();
+                let event_stream = tokio::time::interval(tokio::time::Duration::from_millis(500))
+                    .filter_map(move |_| {
+                        let version = soundboards::get_soundboards_version();
+                        let changed = version != last_version;
+                        last_version = version;
+                        futures::future::ready(if changed {
+                            Some(sse_event("changed".to_string()))
+                        } else {
+                            None
+                        })
+                    });
+                warp::sse::reply(warp::sse::keep_alive().stream(event_stream))
+            });
+
     let hotkey_events_route = warp::path!("hotkeys" / "events")
         .and(warp::get())
         .and(senders_filter.clone())
@@ -919,23 +1470,49 @@ pub async fn run(
         web_path = std::path::PathBuf::from_str("web").unwrap();
     }
 
-    let soundboard_routes = soundboards_route
+    let soundboard_routes = soundboards_events_route
+        .or(soundboards_route)
         .or(soundboards_soundboard_change_route)
+        .or(soundboards_soundboard_undo_route)
+        .or(soundboards_soundboard_redo_route)
         .or(soundboards_soundboard_route);
 
     let soundboard_sound_routes = soundboards_sounds_sound_route
+        .or(soundboards_sounds_icon_route)
         .or(soundboards_soundboard_add_sound_upload_route)
         .or(soundboards_soundboard_add_sound_route)
         .or(soundboards_soundboard_copy_sound_route)
         .or(soundboards_sounds_delete_sound_route)
         .or(soundboards_sounds_change_sound_route);
 
+    #[cfg(feature = "wav")]
+    let soundboard_sound_routes = soundboard_sound_routes.or(sounds_export_route);
+
     let sound_thread_routes = sounds_play_route
         .or(sounds_stop_route)
         .or(sounds_stop_all_route)
+        .or(sounds_pause_all_route)
+        .or(sounds_resume_all_route)
+        .or(sounds_pause_route)
+        .or(sounds_resume_route)
+        .or(sounds_seek_route)
         .or(sounds_active_route)
         .or(sounds_set_volume)
-        .or(sounds_events_route);
+        .or(sounds_events_route)
+        .or(queue_route)
+        .or(queue_add_route)
+        .or(queue_skip_route)
+        .or(queue_clear_route)
+        .or(queue_move_route)
+        .or(queue_remove_route)
+        .or(input_processors_route)
+        .or(input_processors_set_route)
+        .or(effects_route)
+        .or(effects_set_route)
+        .or(targets_route)
+        .or(devices_route)
+        .or(devices_current_route)
+        .or(devices_switch_route);
 
     let hotkey_routes = hotkey_events_route
         .or(hotkey_register_route)
@@ -104,6 +104,29 @@ soundboard encountered an fatal error:
     info!("Auf Wiedersehen!");
 }
 
+/// Renders the sound of the `export` subcommand to a wav file
+#[cfg(feature = "wav")]
+fn export_sound(export: &app_config::ExportCommand) -> Result<()> {
+    let soundboards = soundboards::get_soundboards();
+    let soundboard = soundboards
+        .values()
+        .find(|soundboard| soundboard.get_name() == export.soundboard)
+        .ok_or_else(|| anyhow!("unknown soundboard {}", export.soundboard))?;
+    let sound = soundboard
+        .iter()
+        .find(|sound| sound.get_name() == export.sound)
+        .ok_or_else(|| anyhow!("unknown sound {} in {}", export.sound, export.soundboard))?;
+    let file = std::fs::File::create(&export.output)
+        .with_context(|| format!("failed to create {}", export.output.display()))?;
+    sound::export_sound_wav(sound, std::io::BufWriter::new(file))?;
+    info!(
+        "Exported {} to {}",
+        sound.get_name(),
+        export.output.display()
+    );
+    Ok(())
+}
+
 fn try_main() -> Result<()> {
     let wants_terminal_ui = std::env::args().any(|s| s.contains("--tui=true"));
     // less logging for terminal ui
@@ -132,6 +155,13 @@ fn try_main() -> Result<()> {
     // check for soundboards
     let _ = soundboards::get_soundboards();
 
+    #[cfg(feature = "wav")]
+    {
+        if let Some(export) = app_config::get_export_command() {
+            return export_sound(&export);
+        }
+    }
+
     let (sound_sender, gui_receiver): (
         crossbeam_channel::Sender<sound::Message>,
         crossbeam_channel::Receiver<sound::Message>,
@@ -195,13 +225,14 @@ fn try_main() -> Result<()> {
     #[cfg(not(feature = "autoloop"))]
     let loop_device_id = app_config::get_app_config().loopback_device.clone();
 
-    let loop_device_id = loop_device_id.ok_or_else(|| {
-        anyhow!(
+    if loop_device_id.is_none() {
+        warn!(
             r"No loopback device specified in config file with loopback_device or
                                  in env with SB_LOOPBACK_DEVICE or
-                                 in cmd arguments with --loopback-device"
-        )
-    })?;
+                                 in cmd arguments with --loopback-device,
+                                 sounds are only played on the output device"
+        );
+    }
 
     let gui_sender_clone = gui_sender.clone();
     let input_device_id_clone = app_config::get_app_config().input_device.clone();
@@ -218,13 +249,20 @@ fn try_main() -> Result<()> {
     });
 
     // test for sound thread successfull initialization
-    if let Err(err) = gui_sender.send(sound::Message::PlayStatus(Vec::new(), 0.0)) {
+    if let Err(err) = gui_sender.send(sound::Message::PlayStatus(Vec::new(), Default::default())) {
         return Err(anyhow!(err));
     }
     if let Err(err) = gui_receiver.recv() {
         return Err(anyhow!(err));
     }
 
+    if app_config::get_app_config()
+        .watch_soundboards
+        .unwrap_or_default()
+    {
+        soundboards::watch_soundboards();
+    }
+
     #[cfg(feature = "http")]
     {
         if app_config::get_app_config().http_server.unwrap_or_default() {
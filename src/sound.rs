@@ -1,27 +1,36 @@
 use anyhow::{anyhow, Result};
 use log::{error, info, trace, warn};
+use once_cell::sync::Lazy;
+use std::collections::BTreeSet;
 use std::collections::HashMap;
-use std::io::BufReader;
+use std::collections::VecDeque;
+use std::fmt;
 use std::path::PathBuf;
 use std::str::FromStr;
+use std::sync::atomic::{AtomicBool, AtomicU32, AtomicUsize, Ordering};
 use std::sync::Arc;
 use std::thread::JoinHandle;
 use std::time::Duration;
 
 use super::app_config;
 use super::download;
+use super::hotkey;
 use super::soundboards;
 use super::utils;
 
 mod decoder;
+mod ducking;
+mod effects;
+mod loudness;
+mod push_to_talk;
 mod sample;
 mod sink;
 mod source;
 
 use decoder::Decoder;
-use miniaudio::{Context, DeviceId, DeviceType, ShareMode};
-use sink::Sink;
-use source::Source;
+use miniaudio::{Context, DeviceType, ShareMode};
+use sink::{PlayOptions, ReopenSource, Sink};
+use source::{BufferedSource, FilterExt, PitchShift, ReversedSamples, Source, Speed};
 
 static DEFAULT_BACKENDS: [miniaudio::Backend; 5] = [
     miniaudio::Backend::Wasapi,
@@ -31,29 +40,77 @@ static DEFAULT_BACKENDS: [miniaudio::Backend; 5] = [
     miniaudio::Backend::Alsa,
 ];
 
-fn print_device_info(context: &Context, device_type: DeviceType, device_id: &DeviceId) {
-    // This can fail, so we have to check the result.
-    let info = match context.get_device_info(device_type, device_id, ShareMode::Shared) {
-        Ok(info) => info,
-        Err(err) => {
-            error!("\t\tfailed to get device info: {}", err);
-            return;
-        }
-    };
+/// Direction of an audio device, loop devices are output devices
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AudioDeviceType {
+    Input,
+    Output,
+}
 
-    info!(
-        "\t\t\tSample Rate: {}-{}Hz",
-        info.min_sample_rate(),
-        info.max_sample_rate()
-    );
+/// Audio device of the backend
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct AudioDevice {
+    /// index in the device list of the backend
+    pub id: usize,
+    /// name used to select the device in the config
+    pub name: String,
+    #[serde(rename = "type")]
+    pub device_type: AudioDeviceType,
+    /// the backends list their default device first
+    pub default: bool,
+    pub formats: Vec<String>,
+    pub sample_rates: Option<(u32, u32)>,
+    pub channels: Option<(u32, u32)>,
+}
 
-    info!(
-        "\t\t\tChannels: {}-{}",
-        info.min_channels(),
-        info.max_channels()
-    );
+fn enumerate_devices(context: &Context, with_info: bool) -> Result<Vec<AudioDevice>> {
+    let mut devices = Vec::new();
+    let mut push_devices = |infos: &[miniaudio::DeviceIdAndName], device_type: AudioDeviceType| {
+        let miniaudio_type = match device_type {
+            AudioDeviceType::Input => DeviceType::Capture,
+            AudioDeviceType::Output => DeviceType::Playback,
+        };
+        for (idx, device) in infos.iter().enumerate() {
+            let mut audio_device = AudioDevice {
+                id: idx,
+                name: device.name().to_owned(),
+                device_type,
+                default: idx == 0,
+                formats: Vec::new(),
+                sample_rates: None,
+                channels: None,
+            };
+            if with_info {
+                // This can fail, so we have to check the result.
+                match context.get_device_info(miniaudio_type, device.id(), ShareMode::Shared) {
+                    Ok(info) => {
+                        audio_device.formats =
+                            info.formats().iter().map(|f| format!("{:?}", f)).collect();
+                        audio_device.sample_rates =
+                            Some((info.min_sample_rate(), info.max_sample_rate()));
+                        audio_device.channels = Some((info.min_channels(), info.max_channels()));
+                    }
+                    Err(err) => error!("failed to get device info of {}: {}", device.name(), err),
+                }
+            }
+            devices.push(audio_device);
+        }
+    };
+    context
+        .with_devices(|playback_devices, capture_devices| {
+            push_devices(playback_devices, AudioDeviceType::Output);
+            push_devices(capture_devices, AudioDeviceType::Input);
+        })
+        .map_err(|err| anyhow!("failed to get devices {}", err))?;
+    Ok(devices)
+}
 
-    info!("\t\t\tFormats: {:?}", info.formats());
+/// Returns the input and output devices of the audio backend with their supported formats
+pub fn list_devices() -> Result<Vec<AudioDevice>> {
+    let context = Context::new(&DEFAULT_BACKENDS, None)
+        .map_err(|err| anyhow!("could not create audio context {}", err))?;
+    enumerate_devices(&context, true)
 }
 
 pub fn print_possible_devices_and_exit() {
@@ -64,25 +121,25 @@ pub fn print_possible_devices_and_exit() {
 fn print_possible_devices(context: &Context, full: bool) {
     info!("Audio Backend: {:?}", context.backend());
 
-    context
-        .with_devices(|playback_devices, capture_devices| {
-            info!("\tOutput Devices:");
-            for (idx, device) in playback_devices.iter().enumerate() {
-                info!("\t\t{}: {}", idx, device.name());
-                if full {
-                    print_device_info(&context, DeviceType::Playback, device.id());
-                }
+    let devices = enumerate_devices(context, full).expect("failed to get devices");
+    for (device_type, title) in &[
+        (AudioDeviceType::Output, "Output Devices"),
+        (AudioDeviceType::Input, "Input Devices"),
+    ] {
+        info!("\t{}:", title);
+        for device in devices.iter().filter(|d| d.device_type == *device_type) {
+            info!("\t\t{}: {}", device.id, device.name);
+            if let Some((min, max)) = device.sample_rates {
+                info!("\t\t\tSample Rate: {}-{}Hz", min, max);
             }
-
-            info!("\tInput Devices:");
-            for (idx, device) in capture_devices.iter().enumerate() {
-                info!("\t\t{}: {}", idx, device.name());
-                if full {
-                    print_device_info(&context, DeviceType::Capture, device.id());
-                }
+            if let Some((min, max)) = device.channels {
+                info!("\t\t\tChannels: {}-{}", min, max);
             }
-        })
-        .expect("failed to get devices");
+            if full {
+                info!("\t\t\tFormats: {:?}", device.formats);
+            }
+        }
+    }
 }
 
 pub fn run_sound_loop(
@@ -91,7 +148,7 @@ pub fn run_sound_loop(
     gui_sender: crossbeam_channel::Sender<Message>,
     input_device_identifier: Option<String>,
     output_device_identifier: Option<String>,
-    loop_device_identifier: String,
+    loop_device_identifier: Option<String>,
 ) -> ! {
     let mut context_config = miniaudio::ContextConfig::default();
     context_config
@@ -100,126 +157,521 @@ pub fn run_sound_loop(
         .expect("failed to set pulse app name");
     let context = Context::new(&DEFAULT_BACKENDS, Some(&context_config))
         .expect("could not create audio context");
-    let mut ms_input_device = None;
-    let mut ms_output_device = None;
-    let mut ms_loop_device = None;
 
     info!("Possible Devices: ");
     print_possible_devices(&context, false);
 
-    context
-        .with_devices(|playback_devices, capture_devices| {
-            for (_, device) in playback_devices.iter().enumerate() {
-                if device.name() == loop_device_identifier {
-                    ms_loop_device = Some(device.clone());
+    // set while sounds play on the loop device
+    let ducking_active = Arc::new(AtomicBool::new(false));
+    // push to talk opens and push to mute closes the input while the hotkey is held
+    let config = app_config::get_app_config();
+    let input_open = Arc::new(AtomicBool::new(config.push_to_talk_hotkey.is_none()));
+    let mut hotkey_manager = hotkey::HotkeyManager::new();
+    let push_to_talk = config.push_to_talk_hotkey.is_some() || config.push_to_mute_hotkey.is_some();
+    for (hotkey_string, open_on_press) in config
+        .push_to_talk_hotkey
+        .iter()
+        .map(|hotkey| (hotkey, true))
+        .chain(
+            config
+                .push_to_mute_hotkey
+                .iter()
+                .map(|hotkey| (hotkey, false)),
+        )
+    {
+        let input_open = input_open.clone();
+        let result = hotkey::parse_hotkey(hotkey_string).and_then(|hotkey| {
+            hotkey_manager
+                .register_with_release(hotkey, move |pressed| {
+                    let open = pressed == open_on_press;
+                    input_open.store(open, Ordering::Relaxed);
+                    info!(
+                        "input streamed to loop device {}",
+                        if open { "open" } else { "muted" }
+                    );
+                })
+                .map_err(anyhow::Error::from)
+        });
+        if let Err(err) = result {
+            error!(
+                "register push to talk hotkey {} failed {:#}",
+                hotkey_string, err
+            );
+        }
+    }
+    // pressing the hotkey of the active effect disables it again
+    for (effect, hotkey_string) in VoiceEffect::ALL.iter().zip(
+        [
+            &config.pitch_shift_hotkey,
+            &config.robot_hotkey,
+            &config.reverb_hotkey,
+            &config.echo_hotkey,
+        ]
+        .iter(),
+    ) {
+        if let Some(hotkey_string) = hotkey_string {
+            let effect = *effect;
+            let result = hotkey::parse_hotkey(hotkey_string).and_then(|hotkey| {
+                hotkey_manager
+                    .register(hotkey, move || {
+                        if get_voice_effect() == Some(effect) {
+                            set_voice_effect(None);
+                        } else {
+                            set_voice_effect(Some(effect));
+                        }
+                    })
+                    .map_err(anyhow::Error::from)
+            });
+            if let Err(err) = result {
+                error!(
+                    "register voice effect hotkey {} failed {:#}",
+                    hotkey_string, err
+                );
+            }
+        }
+    }
+
+    let device_names = DeviceNames {
+        input: input_device_identifier,
+        output: output_device_identifier,
+        loopback: loop_device_identifier,
+    };
+    let input_open = if push_to_talk { Some(input_open) } else { None };
+    let mut volumes = Volumes::default();
+    let (device_names, devices) = wait_for_devices(
+        &context,
+        &sound_receiver,
+        &sound_sender,
+        device_names,
+        &ducking_active,
+        &input_open,
+        &mut volumes,
+    );
+
+    run_sound_message_loop(
+        context,
+        sound_receiver,
+        sound_sender,
+        gui_sender,
+        device_names,
+        devices,
+        volumes,
+        ducking_active,
+        input_open,
+    );
+}
+
+/// Opens the devices, retries until they are available
+///
+/// Messages which need no devices are handled meanwhile, sounds played without devices are dropped
+fn wait_for_devices(
+    context: &Context,
+    sound_receiver: &crossbeam_channel::Receiver<Message>,
+    sound_sender: &crossbeam_channel::Sender<Message>,
+    mut device_names: DeviceNames,
+    ducking_active: &Arc<AtomicBool>,
+    input_open: &Option<Arc<AtomicBool>>,
+    volumes: &mut Volumes,
+) -> (DeviceNames, OpenedDevices) {
+    let mut last_attempt: Option<std::time::Instant> = None;
+    loop {
+        if last_attempt.map_or(true, |attempt| attempt.elapsed() >= RECONNECT_INTERVAL) {
+            let first_attempt = last_attempt.is_none();
+            last_attempt = Some(std::time::Instant::now());
+            match open_devices(context, &device_names, ducking_active, input_open) {
+                Ok(devices) => return (device_names, devices),
+                Err(err) => {
+                    let error = format!("{:#}", err);
+                    if first_attempt {
+                        error!("failed to open audio devices, retrying {}", error);
+                    }
+                    DEVICE_STATUS.write().error = Some(error);
                 }
-                if output_device_identifier.is_some()
-                    && device.name() == output_device_identifier.as_ref().unwrap()
-                {
-                    ms_output_device = Some(device.clone());
+            }
+        }
+        match sound_receiver.recv_timeout(Duration::from_millis(100)) {
+            Ok(Message::PlayStatus(_, _)) => {
+                sound_sender
+                    .send(Message::PlayStatus(Vec::new(), *volumes))
+                    .expect("sound channel error");
+            }
+            Ok(Message::SwitchDevices(names)) => {
+                device_names = names;
+                last_attempt = None;
+                if let Err(err) = app_config::save_devices(&device_names) {
+                    warn!("failed to persist devices {:#}", err);
                 }
             }
+            Ok(Message::SetVolume(target, volume)) => {
+                let volume = volume.max(0.0);
+                volumes.set(target, volume);
+                if let VolumeTarget::Input = target {
+                    INPUT_VOLUME.store(volume.to_bits(), Ordering::Relaxed);
+                }
+            }
+            Ok(message) => warn!("no audio devices, dropped {:?}", message),
+            Err(crossbeam_channel::RecvTimeoutError::Timeout) => {}
+            Err(err) => error!("message receive error {}", err),
+        }
+    }
+}
 
-            if input_device_identifier.is_none() {
-                return;
-            };
-            for (_, device) in capture_devices.iter().enumerate() {
-                if device.name() == input_device_identifier.as_ref().unwrap() {
-                    ms_input_device = Some(device.clone());
+/// Names of the devices used by the sound thread
+///
+/// The default device of the backend is used for the input and output without a name
+#[derive(Debug, Clone, PartialEq, Default, serde::Serialize, serde::Deserialize)]
+pub struct DeviceNames {
+    pub input: Option<String>,
+    pub output: Option<String>,
+    pub loopback: Option<String>,
+}
+
+/// Devices used by the sound thread and the names of the devices which could not be found
+///
+/// Without a loop device the sounds are only played on the output device
+#[derive(Debug, Clone, PartialEq, Default, serde::Serialize, serde::Deserialize)]
+pub struct DeviceStatus {
+    pub devices: DeviceNames,
+    pub missing: Vec<String>,
+    pub monitor_only: bool,
+    /// why the devices could not be opened, the old devices are used meanwhile if there are any
+    pub error: Option<String>,
+}
+
+static DEVICE_STATUS: Lazy<parking_lot::RwLock<DeviceStatus>> = Lazy::new(Default::default);
+
+/// Returns the devices used by the sound thread
+///
+/// Missing devices are reconnected as soon as they are available again
+pub fn get_device_status() -> DeviceStatus {
+    DEVICE_STATUS.read().clone()
+}
+
+/// Time between attempts to reopen lost devices
+const RECONNECT_INTERVAL: Duration = Duration::from_secs(2);
+
+type FoundDevices = (
+    Option<miniaudio::DeviceIdAndName>,
+    Option<miniaudio::DeviceIdAndName>,
+    Vec<(String, miniaudio::DeviceIdAndName)>,
+);
+
+/// Resolves the input, output and loop devices by name
+///
+/// Returns the found devices and the names of the devices which could not be found
+fn find_devices(context: &Context, names: &DeviceNames) -> (FoundDevices, Vec<String>) {
+    let mut loop_identifiers: Vec<(String, String)> = names
+        .loopback
+        .iter()
+        .map(|loopback| (LOOP_TARGET.to_owned(), loopback.clone()))
+        .collect();
+    loop_identifiers.extend(
+        app_config::get_app_config()
+            .loopback_devices
+            .clone()
+            .unwrap_or_default()
+            .into_iter()
+            .filter(|(name, _)| {
+                let reserved = name == OUTPUT_TARGET || name == LOOP_TARGET || name.contains(',');
+                if reserved {
+                    error!("invalid name for loopback device \"{}\"", name);
                 }
+                !reserved
+            }),
+    );
+
+    let mut input_device = None;
+    let mut output_device = None;
+    let mut loop_devices: Vec<Option<miniaudio::DeviceIdAndName>> =
+        vec![None; loop_identifiers.len()];
+    let result = context.with_devices(|playback_devices, capture_devices| {
+        for device in playback_devices.iter() {
+            if Some(device.name()) == names.output.as_deref() {
+                output_device = Some(device.clone());
             }
-        })
-        .expect("failed to create context");
+            for (index, (_, identifier)) in loop_identifiers.iter().enumerate() {
+                if device.name() == identifier {
+                    loop_devices[index] = Some(device.clone());
+                }
+            }
+        }
+        for device in capture_devices.iter() {
+            if Some(device.name()) == names.input.as_deref() {
+                input_device = Some(device.clone());
+            }
+        }
+    });
+    if let Err(err) = result {
+        error!("failed to get devices {}", err);
+    }
 
-    if ms_loop_device.is_none() {
-        panic!(
-            "Could not find loop device identifier \"{}\"",
-            loop_device_identifier
-        );
+    let mut missing = Vec::new();
+    if let (Some(name), None) = (names.input.as_ref(), input_device.as_ref()) {
+        missing.push(name.clone());
+    }
+    if let (Some(name), None) = (names.output.as_ref(), output_device.as_ref()) {
+        missing.push(name.clone());
+    }
+    let mut found_loop_devices = Vec::new();
+    for ((name, identifier), device) in loop_identifiers.into_iter().zip(loop_devices) {
+        match device {
+            Some(device) => found_loop_devices.push((name, device)),
+            None => missing.push(identifier),
+        }
     }
+    ((input_device, output_device, found_loop_devices), missing)
+}
 
-    if input_device_identifier.is_some() && ms_input_device.is_none() {
-        panic!(
-            "Could not find input device identifier \"{}\"",
-            input_device_identifier.unwrap()
-        );
+/// Sinks of the output and loop devices and the input streamed to the first loop device
+struct OpenedDevices {
+    output_device: Option<miniaudio::DeviceIdAndName>,
+    output_sink: SinkDecoder,
+    loopback_sinks: Vec<(String, miniaudio::DeviceIdAndName, SinkDecoder)>,
+    duplex_device: Option<miniaudio::Device>,
+    /// loop devices which could not be found and are opened as soon as they are available
+    missing: Vec<String>,
+}
+
+impl OpenedDevices {
+    /// Returns the sink of `loopback_device` which also receives the input
+    fn primary_loop_sink(&mut self) -> Option<&mut SinkDecoder> {
+        self.loopback_sinks
+            .iter_mut()
+            .find(|(name, _, _)| name == LOOP_TARGET)
+            .map(|(_, _, sink)| sink)
     }
 
-    if output_device_identifier.is_some() && ms_output_device.is_none() {
-        panic!(
-            "Could not find output device identifier \"{}\"",
-            output_device_identifier.unwrap()
-        );
+    /// Returns whether a device stopped and could not be started again
+    fn lost(&self) -> bool {
+        (self.output_sink.stopped() && self.output_sink.start().is_err())
+            || self
+                .loopback_sinks
+                .iter()
+                .any(|(_, _, sink)| sink.stopped() && sink.start().is_err())
+            || self.duplex_device.as_ref().map_or(false, |device| {
+                !device.is_started() && device.start().is_err()
+            })
+    }
+
+    fn set_volumes(&self, volumes: &Volumes) -> Result<()> {
+        self.output_sink.set_volume(volumes.output)?;
+        for (_, _, sink) in self.loopback_sinks.iter() {
+            sink.set_volume(volumes.loopback)?;
+        }
+        Ok(())
     }
+}
 
-    if let Some(input_device) = ms_input_device.as_ref() {
+/// Opens the named devices and updates the device status
+///
+/// Missing loop devices are skipped so sounds can still be played on the output device
+fn open_devices(
+    context: &Context,
+    names: &DeviceNames,
+    ducking_active: &Arc<AtomicBool>,
+    input_open: &Option<Arc<AtomicBool>>,
+) -> Result<OpenedDevices> {
+    let ((input_device, output_device, loop_devices), missing) = find_devices(context, names);
+    let input_missing = names.input.is_some() && input_device.is_none();
+    if input_missing || (names.output.is_some() && output_device.is_none()) {
+        let err = anyhow!("Could not find devices {:?}", missing);
+        *DEVICE_STATUS.write() = DeviceStatus {
+            devices: names.clone(),
+            missing,
+            monitor_only: false,
+            error: Some(format!("{:#}", err)),
+        };
+        return Err(err);
+    }
+    if !missing.is_empty() {
+        warn!("Could not find loop devices {:?}", missing);
+    }
+
+    if let Some(input_device) = input_device.as_ref() {
         info!("Input device: \"{}\"", input_device.name());
     } else {
         info!("Input device: default input device");
     }
-    if let Some(output_device) = ms_output_device.as_ref() {
+    if let Some(output_device) = output_device.as_ref() {
         info!("Output device: \"{}\"", output_device.name());
     } else {
         info!("Output device: default output device");
     }
-    info!(
-        "Loop device: \"{}\"",
-        ms_loop_device.as_ref().unwrap().name()
-    );
+    for (name, device) in loop_devices.iter() {
+        info!("Loop device {}: \"{}\"", name, device.name());
+    }
+    if loop_devices.is_empty() {
+        warn!("No loop device, sounds are only played on the output device");
+    }
 
-    let loop_back_device = {
-        if app_config::get_app_config()
-            .stream_input_to_loop
-            .unwrap_or_default()
-        {
-            let ms_loop_device_clone = ms_loop_device.clone();
-            Some(
-                create_duplex_device(&context, ms_input_device, ms_loop_device_clone.unwrap())
-                    .expect("create duplex device failed"),
-            )
-        } else {
-            None
+    let config = app_config::get_app_config();
+    let output_sink = SinkDecoder::new(
+        context,
+        output_device.as_ref().map(|device| device.id().clone()),
+        config.output_headroom.unwrap_or_default(),
+    )?;
+    output_sink.start()?;
+
+    // the first loop device also receives the input
+    let mut loopback_sinks = Vec::new();
+    for (name, device) in loop_devices.into_iter() {
+        let sink = SinkDecoder::new(
+            context,
+            Some(device.id().clone()),
+            config.loopback_headroom.unwrap_or_default(),
+        )?;
+        sink.start()?;
+        loopback_sinks.push((name, device, sink));
+    }
+
+    let primary_loop_device = loopback_sinks
+        .iter()
+        .find(|(name, _, _)| name == LOOP_TARGET)
+        .map(|(_, device, _)| device.clone());
+    let duplex_device = match primary_loop_device {
+        Some(loop_device) if config.stream_input_to_loop.unwrap_or_default() => {
+            Some(create_duplex_device(
+                context,
+                input_device,
+                loop_device,
+                ducking_active.clone(),
+                input_open.clone(),
+            )?)
         }
+        _ => None,
     };
 
-    run_sound_message_loop(
-        context,
-        sound_receiver,
-        sound_sender,
-        gui_sender,
-        ms_loop_device.unwrap(),
-        ms_output_device,
-        loop_back_device,
-    );
+    *DEVICE_STATUS.write() = DeviceStatus {
+        devices: names.clone(),
+        missing: missing.clone(),
+        monitor_only: loopback_sinks.is_empty(),
+        error: None,
+    };
+    Ok(OpenedDevices {
+        output_device,
+        output_sink,
+        loopback_sinks,
+        duplex_device,
+        missing,
+    })
 }
 
 type StartedTime = std::time::Instant;
 type SoundMap = HashMap<soundboards::SoundId, (SoundStatus, StartedTime, Option<TotalDuration>)>;
 
-#[derive(
-    Debug,
-    serde::Deserialize,
-    Copy,
-    Clone,
-    serde::Serialize,
-    strum_macros::EnumString,
-    PartialEq,
-    Hash,
-    Eq,
-)]
+/// Name of the routing target of the output device
+pub const OUTPUT_TARGET: &str = "output";
+/// Name of the routing target of the `loopback_device`
+pub const LOOP_TARGET: &str = "loop";
+
+/// Devices a sound is played on
+///
+/// Serialized as `Loop`, `Output`, `Both` or a comma separated list of routing target names
+#[derive(Debug, Clone, PartialEq, Hash, Eq, PartialOrd, Ord)]
 pub enum SoundDevices {
+    /// All loop devices
     Loop,
     Output,
+    /// The output device and all loop devices
     Both,
+    /// Named routing targets, see `get_routing_targets`
+    Targets(BTreeSet<String>),
+}
+
+impl SoundDevices {
+    /// Returns whether the sound is played on the output device
+    pub fn includes_output(&self) -> bool {
+        match self {
+            SoundDevices::Output | SoundDevices::Both => true,
+            SoundDevices::Loop => false,
+            SoundDevices::Targets(targets) => targets.contains(OUTPUT_TARGET),
+        }
+    }
+
+    /// Returns whether the sound is played on the named loop device
+    pub fn includes_loop(&self, name: &str) -> bool {
+        match self {
+            SoundDevices::Loop | SoundDevices::Both => true,
+            SoundDevices::Output => false,
+            SoundDevices::Targets(targets) => targets.contains(name),
+        }
+    }
+}
+
+impl fmt::Display for SoundDevices {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SoundDevices::Loop => write!(f, "Loop"),
+            SoundDevices::Output => write!(f, "Output"),
+            SoundDevices::Both => write!(f, "Both"),
+            SoundDevices::Targets(targets) => {
+                write!(
+                    f,
+                    "{}",
+                    targets.iter().cloned().collect::<Vec<_>>().join(",")
+                )
+            }
+        }
+    }
+}
+
+impl FromStr for SoundDevices {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "Loop" => Ok(SoundDevices::Loop),
+            "Output" => Ok(SoundDevices::Output),
+            "Both" => Ok(SoundDevices::Both),
+            s => {
+                let targets: BTreeSet<String> = s
+                    .split(',')
+                    .map(|target| target.trim().to_owned())
+                    .filter(|target| !target.is_empty())
+                    .collect();
+                if targets.is_empty() {
+                    return Err(anyhow!("no routing targets in \"{}\"", s));
+                }
+                Ok(SoundDevices::Targets(targets))
+            }
+        }
+    }
+}
+
+impl serde::Serialize for SoundDevices {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.to_string())
+    }
+}
+
+impl<'de> serde::Deserialize<'de> for SoundDevices {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        s.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+/// Returns the names of all routing targets
+///
+/// The output device, the `loopback_device` and the named `loopback_devices`
+pub fn get_routing_targets() -> Vec<String> {
+    let config = app_config::get_app_config();
+    let mut targets = vec![OUTPUT_TARGET.to_owned(), LOOP_TARGET.to_owned()];
+    if let Some(loopback_devices) = config.loopback_devices.as_ref() {
+        targets.extend(loopback_devices.keys().cloned());
+    }
+    targets
 }
 
 type PlayDuration = std::time::Duration;
 type TotalDuration = std::time::Duration;
+type LoopCount = u32;
 
 #[derive(Debug, PartialEq, Eq, serde::Deserialize, Copy, Clone, serde::Serialize)]
 pub enum SoundStatus {
     Downloading,
     Playing,
+    Paused,
 }
 
 pub type PlayStatusVecType = Vec<(
@@ -227,38 +679,254 @@ pub type PlayStatusVecType = Vec<(
     soundboards::SoundId,
     PlayDuration,
     Option<TotalDuration>,
+    LoopCount,
 )>;
 
+/// Upcoming sounds per devices
+pub type QueueType = HashMap<SoundDevices, VecDeque<soundboards::SoundId>>;
+
+static GLOBAL_QUEUE: Lazy<parking_lot::RwLock<QueueType>> = Lazy::new(Default::default);
+
+/// Returns the upcoming sounds of the queue
+///
+/// Sounds are only queued with the queue_playback option or with `Message::Enqueue`
+pub fn get_queue() -> QueueType {
+    GLOBAL_QUEUE.read().clone()
+}
+
+/// Processors of the input streamed to the loop device
+#[derive(Debug, serde::Deserialize, Copy, Clone, serde::Serialize, PartialEq, Hash, Eq)]
+pub enum InputProcessor {
+    NoiseGate,
+    HighPass,
+    Compressor,
+}
+
+impl InputProcessor {
+    pub const ALL: [InputProcessor; 3] = [
+        InputProcessor::NoiseGate,
+        InputProcessor::HighPass,
+        InputProcessor::Compressor,
+    ];
+}
+
+static INPUT_PROCESSORS_ENABLED: Lazy<[AtomicBool; 3]> = Lazy::new(|| {
+    let config = app_config::get_app_config();
+    [
+        AtomicBool::new(config.noise_gate.unwrap_or_default()),
+        AtomicBool::new(config.high_pass.unwrap_or_default()),
+        AtomicBool::new(config.compressor.unwrap_or_default()),
+    ]
+});
+
+/// Returns whether the input processors are enabled
+pub fn get_input_processors() -> Vec<(InputProcessor, bool)> {
+    InputProcessor::ALL
+        .iter()
+        .map(|processor| {
+            (
+                *processor,
+                INPUT_PROCESSORS_ENABLED[*processor as usize].load(Ordering::Relaxed),
+            )
+        })
+        .collect()
+}
+
+/// Voice effects for the input streamed to the loop device
+#[derive(Debug, serde::Deserialize, Copy, Clone, serde::Serialize, PartialEq, Hash, Eq)]
+pub enum VoiceEffect {
+    PitchShift,
+    Robot,
+    Reverb,
+    Echo,
+}
+
+impl VoiceEffect {
+    pub const ALL: [VoiceEffect; 4] = [
+        VoiceEffect::PitchShift,
+        VoiceEffect::Robot,
+        VoiceEffect::Reverb,
+        VoiceEffect::Echo,
+    ];
+}
+
+// index into VoiceEffect::ALL plus one, zero means no effect
+static VOICE_EFFECT: AtomicUsize = AtomicUsize::new(0);
+
+/// Returns the voice effect applied to the input
+pub fn get_voice_effect() -> Option<VoiceEffect> {
+    match VOICE_EFFECT.load(Ordering::Relaxed) {
+        0 => None,
+        index => VoiceEffect::ALL.get(index - 1).copied(),
+    }
+}
+
+fn set_voice_effect(effect: Option<VoiceEffect>) {
+    VOICE_EFFECT.store(
+        effect.map_or(0, |effect| effect as usize + 1),
+        Ordering::Relaxed,
+    );
+    info!("voice effect {:?}", effect);
+}
+
+/// Devices with a separately adjustable volume
+#[derive(Debug, serde::Deserialize, Copy, Clone, serde::Serialize, PartialEq, Hash, Eq)]
+pub enum VolumeTarget {
+    /// The local monitor output device
+    Output,
+    /// The loopback device which is heard by others
+    Loopback,
+    /// The microphone passthrough to the loopback device
+    Input,
+}
+
+/// Current volumes of the devices
+#[derive(Debug, serde::Deserialize, Copy, Clone, serde::Serialize, PartialEq)]
+pub struct Volumes {
+    pub output: f32,
+    pub loopback: f32,
+    pub input: f32,
+}
+
+impl Default for Volumes {
+    fn default() -> Self {
+        Self {
+            output: 1.0,
+            loopback: 1.0,
+            input: 1.0,
+        }
+    }
+}
+
+impl Volumes {
+    pub fn get(&self, target: VolumeTarget) -> f32 {
+        match target {
+            VolumeTarget::Output => self.output,
+            VolumeTarget::Loopback => self.loopback,
+            VolumeTarget::Input => self.input,
+        }
+    }
+
+    pub fn set(&mut self, target: VolumeTarget, volume: f32) {
+        match target {
+            VolumeTarget::Output => self.output = volume,
+            VolumeTarget::Loopback => self.loopback = volume,
+            VolumeTarget::Input => self.input = volume,
+        }
+    }
+}
+
+// bits of the f32 volume of the input passthrough
+static INPUT_VOLUME: AtomicU32 = AtomicU32::new(0x3f80_0000);
+
 #[derive(Debug, PartialEq)]
 pub enum Message {
     PlaySound(soundboards::SoundId, SoundDevices),
     StopSound(soundboards::SoundId),
     StopAll,
-    SetVolume(f32),
-    PlayStatus(PlayStatusVecType, f32),
-    _PlaySoundDownloaded(soundboards::SoundId, SoundDevices, std::path::PathBuf),
+    PauseSound(soundboards::SoundId),
+    ResumeSound(soundboards::SoundId),
+    PauseAll,
+    ResumeAll,
+    SeekSound(soundboards::SoundId, Duration),
+    Enqueue(soundboards::SoundId, SoundDevices),
+    Skip(SoundDevices),
+    ClearQueue(SoundDevices),
+    MoveInQueue(SoundDevices, usize, usize),
+    RemoveFromQueue(SoundDevices, usize),
+    SetInputProcessor(InputProcessor, bool),
+    SetVoiceEffect(Option<VoiceEffect>),
+    SwitchDevices(DeviceNames),
+    SetVolume(VolumeTarget, f32),
+    PlayStatus(PlayStatusVecType, Volumes),
+    /// Sound which is available locally with the gain of the loudness normalization and the
+    /// decoded samples of reversed sounds
+    _PlaySoundDownloaded(
+        soundboards::SoundId,
+        SoundDevices,
+        std::path::PathBuf,
+        f32,
+        Option<ReversedSamples<i16>>,
+    ),
 }
 
-fn insert_sink_with_config(
+/// Returns the gain to reach the configured target loudness or 1.0 if normalization is disabled
+///
+/// Decodes the whole file if the loudness was not measured before
+fn loudness_normalization_gain(path: &std::path::Path) -> f32 {
+    let config = app_config::get_app_config();
+    if !config.loudness_normalization.unwrap_or_default() {
+        return 1.0;
+    }
+    match loudness::cached_integrated_loudness(path) {
+        Ok(Some(loudness)) => loudness::gain_for_target_loudness(
+            loudness,
+            config
+                .target_loudness
+                .unwrap_or(app_config::DEFAULT_TARGET_LOUDNESS),
+        ),
+        Ok(None) => 1.0,
+        Err(err) => {
+            warn!(
+                "failed to measure loudness of {}: {:#}",
+                path.display(),
+                err
+            );
+            1.0
+        }
+    }
+}
+
+/// Decodes the samples of reversed sounds
+///
+/// Decodes the whole file, the samples are shared by all plays of the sound
+fn reversed_samples(
     path: &std::path::Path,
-    device: Option<miniaudio::DeviceIdAndName>,
-    sink: &mut SinkDecoder,
     sound: &soundboards::Sound,
-    sinks: &mut SoundMap,
-) -> Result<()> {
-    let device_name = {
-        if let Some(device) = device.as_ref() {
-            device.name().to_string()
-        } else {
-            "default output".to_string()
-        }
+) -> Result<Option<ReversedSamples<i16>>> {
+    if !sound.get_reverse().unwrap_or_default() {
+        return Ok(None);
+    }
+    let reader = std::io::BufReader::with_capacity(1000 * 50, std::fs::File::open(path)?);
+    Ok(Some(ReversedSamples::new(Decoder::new(reader)?)))
+}
+
+/// Opens the decoder or the reversed samples and applies the pitch and speed of the sound
+fn sound_source(
+    path: &std::path::Path,
+    sound: &soundboards::Sound,
+    reversed: Option<&ReversedSamples<i16>>,
+) -> Result<SoundSource> {
+    let source: SoundSource = if let Some(reversed) = reversed {
+        Box::new(reversed.source())
+    } else {
+        let reader = std::io::BufReader::with_capacity(1000 * 50, std::fs::File::open(path)?);
+        Box::new(Decoder::new(reader)?)
     };
-    info!(
-        "Playing sound config: {:?} on device: {}",
-        sound.get_name(),
-        device_name
-    );
+    Ok(apply_effects(source, sound))
+}
 
+/// Applies the pitch and speed of the sound
+fn apply_effects(mut source: SoundSource, sound: &soundboards::Sound) -> SoundSource {
+    if let Some(pitch) = sound.get_pitch() {
+        source = Box::new(PitchShift::new(source, pitch));
+    }
+    if let Some(speed) = sound.get_speed() {
+        source = Box::new(Speed::new(source, speed));
+    }
+    source
+}
+
+/// Opens the sound with its effects and converts its settings to play options
+///
+/// `reversed` are the samples of reversed sounds from `reversed_samples`.
+/// Returns the duration of one play of the trimmed sound if known
+fn open_sound(
+    path: &std::path::Path,
+    sound: &soundboards::Sound,
+    loudness_gain: f32,
+    reversed: Option<&ReversedSamples<i16>>,
+) -> Result<(SoundSource, PlayOptions, Option<Duration>)> {
     if let Some(start) = sound.get_start() {
         if start < 0.0 {
             return Err(anyhow!("error: start timestamp is negative {}", start));
@@ -271,10 +939,28 @@ fn insert_sink_with_config(
         }
     }
 
-    let reader = std::io::BufReader::with_capacity(1000 * 50, std::fs::File::open(path)?);
-    let mut decoder = Decoder::new(reader)?;
-    let mut reader = std::io::BufReader::with_capacity(1000 * 50, std::fs::File::open(path)?);
-    let total_duration = decoder.total_duration_mut(&mut reader);
+    // reversed sounds are decoded completely which gives the exact duration of the file
+    let (source, reversed_duration, total_duration) = if let Some(reversed) = reversed {
+        let reversed = reversed.source();
+        let duration = reversed
+            .total_duration()
+            .ok_or_else(|| anyhow!("error: duration of the reversed sound is unknown"))?;
+        (
+            apply_effects(Box::new(reversed), sound),
+            Some(duration),
+            Some(duration),
+        )
+    } else {
+        let reader = std::io::BufReader::with_capacity(1000 * 50, std::fs::File::open(path)?);
+        let mut decoder = Decoder::new(reader)?;
+        let mut reader = std::io::BufReader::with_capacity(1000 * 50, std::fs::File::open(path)?);
+        let total_duration = decoder.total_duration_mut(&mut reader);
+        (
+            apply_effects(Box::new(decoder), sound),
+            None,
+            total_duration,
+        )
+    };
     let total_duration = match (total_duration, sound.get_start(), sound.get_end()) {
         (Some(total_duration), Some(start), None) => {
             if let Some(duration) = total_duration.checked_sub(Duration::from_secs_f32(start)) {
@@ -312,7 +998,77 @@ fn insert_sink_with_config(
         }
         (None, _, None) => None,
     };
-    sink.play(*sound.get_id(), decoder, sound.get_start(), sound.get_end())?;
+    let speed = sound.get_speed().unwrap_or(1.0);
+    let total_duration = total_duration.map(|duration| duration.div_f32(speed));
+
+    // start and end are timestamps of the file, the sink expects them in playback time
+    let (start, end) = if let Some(file_duration) = reversed_duration {
+        let file_duration = file_duration.as_secs_f32();
+        (
+            sound.get_end().map(|end| (file_duration - end).max(0.0)),
+            sound.get_start().map(|start| file_duration - start),
+        )
+    } else {
+        (sound.get_start(), sound.get_end())
+    };
+
+    let volume = sound.get_volume().unwrap_or(1.0) * loudness_gain;
+    let config = app_config::get_app_config();
+    let options = PlayOptions {
+        start: start.map(|start| start / speed),
+        end: end.map(|end| end / speed),
+        volume,
+        fade_in: sound.get_fade_in().or(config.fade_in).unwrap_or_default(),
+        fade_out: sound.get_fade_out().or(config.fade_out).unwrap_or_default(),
+        plays: sound.get_loop_mode().and_then(|mode| mode.plays()),
+    };
+    Ok((source, options, total_duration))
+}
+
+/// Returns a reopen function for sounds which are played more than once
+fn reopen_sound(
+    path: &std::path::Path,
+    sound: &soundboards::Sound,
+    options: &PlayOptions,
+    reversed: Option<&ReversedSamples<i16>>,
+) -> Option<ReopenSource<SoundSource>> {
+    if options.plays != Some(1) {
+        let path = path.to_path_buf();
+        let sound = sound.clone();
+        let reversed = reversed.cloned();
+        Some(Box::new(move || -> Result<SoundSource> {
+            sound_source(&path, &sound, reversed.as_ref())
+        }))
+    } else {
+        None
+    }
+}
+
+fn insert_sink_with_config(
+    path: &std::path::Path,
+    device: Option<miniaudio::DeviceIdAndName>,
+    sink: &mut SinkDecoder,
+    sound: &soundboards::Sound,
+    loudness_gain: f32,
+    reversed: Option<&ReversedSamples<i16>>,
+    sinks: &mut SoundMap,
+) -> Result<()> {
+    let device_name = {
+        if let Some(device) = device.as_ref() {
+            device.name().to_string()
+        } else {
+            "default output".to_string()
+        }
+    };
+    info!(
+        "Playing sound config: {:?} on device: {}",
+        sound.get_name(),
+        device_name
+    );
+
+    let (source, options, total_duration) = open_sound(path, sound, loudness_gain, reversed)?;
+    let reopen = reopen_sound(path, sound, &options, reversed);
+    sink.play(*sound.get_id(), source, options, reopen)?;
 
     match sinks.entry(*sound.get_id()) {
         std::collections::hash_map::Entry::Occupied(mut entry) => {
@@ -332,209 +1088,485 @@ fn insert_sink_with_config(
     Ok(())
 }
 
-type SinkDecoder = Sink<soundboards::SoundId, Decoder<std::io::BufReader<std::fs::File>>>;
+/// Renders the sound with its trims, effects, volume and fades to a 16 bit wav file
+///
+/// Sounds which are not available locally are downloaded first, blocks until the sound is rendered
+#[cfg(feature = "wav")]
+pub fn export_sound_wav<W: std::io::Write + std::io::Seek>(
+    sound: &soundboards::Sound,
+    writer: W,
+) -> Result<()> {
+    let path = download::get_local_path_from_sound_config(sound, true)?
+        .ok_or_else(|| anyhow!("no local path for sound {}", sound.get_name()))?;
+    let reversed = reversed_samples(&path, sound)?;
+    let (source, options, _) = open_sound(
+        &path,
+        sound,
+        loudness_normalization_gain(&path),
+        reversed.as_ref(),
+    )?;
+    let spec = hound::WavSpec {
+        channels: source.channels(),
+        sample_rate: source.sample_rate(),
+        bits_per_sample: 16,
+        sample_format: hound::SampleFormat::Int,
+    };
+    let mut wav_writer = hound::WavWriter::new(writer, spec)?;
+    let reopen = reopen_sound(&path, sound, &options, reversed.as_ref());
+    sink::render(source, options, reopen, |sample| {
+        let sample = (sample.max(-1.0).min(1.0) * i16::MAX as f32) as i16;
+        wav_writer.write_sample(sample).map_err(anyhow::Error::from)
+    })?;
+    wav_writer.finalize()?;
+    Ok(())
+}
+
+/// Measures the loudness and decodes the reversed samples of the sound for all of its sinks
+///
+/// Blocks until the sound is decoded, stops the sound if it can not be decoded
+fn send_sound_prepared(
+    gui_sender: &crossbeam_channel::Sender<Message>,
+    sound: &soundboards::Sound,
+    sound_devices: SoundDevices,
+    path: PathBuf,
+) {
+    let sound_id = *sound.get_id();
+    match reversed_samples(&path, sound) {
+        Ok(reversed) => {
+            let loudness_gain = loudness_normalization_gain(&path);
+            gui_sender
+                .send(Message::_PlaySoundDownloaded(
+                    sound_id,
+                    sound_devices,
+                    path,
+                    loudness_gain,
+                    reversed,
+                ))
+                .expect("sound channel send error");
+        }
+        Err(err) => {
+            gui_sender
+                .send(Message::StopSound(sound_id))
+                .expect("sound channel error");
+            error!("failed to reverse sound {}: {:#}", path.display(), err)
+        }
+    }
+}
+
+/// Starts playing the sound, sounds which are not available locally are downloaded first
+fn start_sound(
+    sound_id: soundboards::SoundId,
+    sound_devices: SoundDevices,
+    sinks: &mut SoundMap,
+    gui_sender: &crossbeam_channel::Sender<Message>,
+) -> Result<()> {
+    let sound = soundboards::find_sound(sound_id).ok_or_else(|| anyhow!("unknown sound_id"))?;
+    let maybe_path = download::get_local_path_from_sound_config(&sound, false)
+        .map_err(|err| anyhow!("local_path_for_sound_config_exists error {}", err))?;
+
+    if let Some(path) = maybe_path {
+        // mark the sound as pending until the sinks picked it up
+        sinks.entry(sound_id).or_insert((
+            SoundStatus::Downloading,
+            std::time::Instant::now(),
+            None,
+        ));
+        // measuring the loudness and reversing the sound would block the sound thread
+        let gui_sender_clone = gui_sender.clone();
+        std::thread::spawn(move || {
+            send_sound_prepared(&gui_sender_clone, &sound, sound_devices, path);
+        });
+    } else {
+        match sinks.entry(sound_id) {
+            std::collections::hash_map::Entry::Occupied(_) => {
+                return Err(anyhow!("sound is already downloading"));
+            }
+            std::collections::hash_map::Entry::Vacant(entry) => {
+                entry.insert((SoundStatus::Downloading, std::time::Instant::now(), None));
+            }
+        }
+        let gui_sender_clone = gui_sender.clone();
+        std::thread::spawn(move || {
+            match download::get_local_path_from_sound_config(&sound, true) {
+                Ok(Some(path)) => {
+                    send_sound_prepared(&gui_sender_clone, &sound, sound_devices, path);
+                }
+                Ok(None) => {
+                    gui_sender_clone
+                        .send(Message::StopSound(sound_id))
+                        .expect("sound channel error");
+                    error!("get_local_path_from_sound_config failed unknown error")
+                }
+                Err(err) => {
+                    gui_sender_clone
+                        .send(Message::StopSound(sound_id))
+                        .expect("sound channel error");
+                    error!("get_local_path_from_sound_config failed: {:#}", err)
+                }
+            }
+        });
+    }
+    Ok(())
+}
+
+/// Removes the sound if it never started playing
+fn remove_pending_sound(sinks: &mut SoundMap, sound_id: soundboards::SoundId) {
+    if let Some((SoundStatus::Downloading, _, _)) = sinks.get(&sound_id) {
+        sinks.remove(&sound_id);
+    }
+}
+
+/// Stops all playing sounds, the queue is kept
+fn stop_all_sounds(sinks: &mut SoundMap, devices: &mut OpenedDevices) {
+    for (key, _) in sinks.drain() {
+        devices.output_sink.remove(&key);
+        for (_, _, loopback_sink) in devices.loopback_sinks.iter_mut() {
+            loopback_sink.remove(&key);
+        }
+    }
+}
+
+type SoundSource = Box<dyn Source<Item = i16> + Send + Sync>;
+type SinkDecoder = Sink<soundboards::SoundId, SoundSource>;
 
 fn run_sound_message_loop(
     context: Context,
     sound_receiver: crossbeam_channel::Receiver<Message>,
     sound_sender: crossbeam_channel::Sender<Message>,
     gui_sender: crossbeam_channel::Sender<Message>,
-    loop_device: miniaudio::DeviceIdAndName,
-    output_device: Option<miniaudio::DeviceIdAndName>,
-    loopback_device: Option<miniaudio::Device>,
+    mut device_names: DeviceNames,
+    mut devices: OpenedDevices,
+    mut volumes: Volumes,
+    ducking_active: Arc<AtomicBool>,
+    input_open: Option<Arc<AtomicBool>>,
 ) -> ! {
-    let mut volume: f32 = 1.0;
+    if let Err(err) = devices.set_volumes(&volumes) {
+        error!("failed to set volumes {:#}", err);
+    }
     let mut sinks: SoundMap = HashMap::new();
-
-    let output_device_id = {
-        if let Some(device) = output_device.clone() {
-            Some(device.id().clone())
-        } else {
-            None
-        }
-    };
-
-    let mut output_sink =
-        SinkDecoder::new(&context, output_device_id).expect("failed to create output sink");
-    output_sink.start().expect("failed to start output_sink");
-
-    let mut loopback_sink = SinkDecoder::new(&context, Some(loop_device.id().clone()))
-        .expect("failed to create output sink");
-    loopback_sink
-        .start()
-        .expect("failed to start loopback_sink");
+    // the sound started from the queue for the devices
+    let mut queue_current: HashMap<SoundDevices, soundboards::SoundId> = HashMap::new();
+    let mut devices_lost = false;
+    let mut last_reconnect = std::time::Instant::now();
 
     loop {
-        match sound_receiver.recv() {
+        // time out to start the next queued sound after a sound ended
+        match sound_receiver.recv_timeout(Duration::from_millis(100)) {
             Ok(message) => match message {
                 Message::PlaySound(sound_id, sound_devices) => {
-                    let sound = {
-                        let sound = soundboards::find_sound(sound_id);
-                        if sound.is_none() {
-                            error!("unknown sound_id");
-                            continue;
+                    let config = app_config::get_app_config();
+                    if config.queue_playback.unwrap_or_default() {
+                        GLOBAL_QUEUE
+                            .write()
+                            .entry(sound_devices)
+                            .or_default()
+                            .push_back(sound_id);
+                    } else {
+                        if !config.simultaneous_playback.unwrap_or_default() {
+                            stop_all_sounds(&mut sinks, &mut devices);
                         }
-                        sound.unwrap()
-                    };
-                    let maybe_path = {
-                        let result = download::get_local_path_from_sound_config(&sound, false);
-                        if let Err(err) = result {
-                            error!("local_path_for_sound_config_exists error {}", err);
+                        if let Err(err) =
+                            start_sound(sound_id, sound_devices, &mut sinks, &gui_sender)
+                        {
+                            error!("failed to play sound {:#}", err);
                             continue;
                         }
-                        result.unwrap()
-                    };
-
-                    if !app_config::get_app_config()
-                        .simultaneous_playback
-                        .unwrap_or_default()
-                    {
-                        gui_sender
-                            .send(Message::StopAll)
-                            .expect("error stopping all playing sounds");
                     }
-
-                    if let Some(path) = maybe_path {
-                        gui_sender
-                            .send(Message::_PlaySoundDownloaded(sound_id, sound_devices, path))
-                            .expect("sound channel send error");
-                    } else {
-                        match sinks.entry(sound_id) {
-                            std::collections::hash_map::Entry::Occupied(_) => {
-                                panic!("sink should not be occupied");
-                            }
-                            std::collections::hash_map::Entry::Vacant(entry) => {
-                                entry.insert((
-                                    SoundStatus::Downloading,
-                                    std::time::Instant::now(),
-                                    None,
-                                ));
+                }
+                Message::Enqueue(sound_id, sound_devices) => {
+                    GLOBAL_QUEUE
+                        .write()
+                        .entry(sound_devices)
+                        .or_default()
+                        .push_back(sound_id);
+                }
+                Message::Skip(sound_devices) => {
+                    if let Some(sound_id) = queue_current.remove(&sound_devices) {
+                        if sinks.remove(&sound_id).is_some() {
+                            devices.output_sink.remove(&sound_id);
+                            for (_, _, loopback_sink) in devices.loopback_sinks.iter_mut() {
+                                loopback_sink.remove(&sound_id);
                             }
                         }
-                        let gui_sender_clone = gui_sender.clone();
-                        std::thread::spawn(
-                            move || match download::get_local_path_from_sound_config(&sound, true) {
-                                Ok(Some(path)) => {
-                                    gui_sender_clone
-                                        .send(Message::_PlaySoundDownloaded(
-                                            sound_id,
-                                            sound_devices,
-                                            path,
-                                        ))
-                                        .expect("sound channel send error");
-                                }
-                                Ok(None) => {
-                                    gui_sender_clone
-                                        .send(Message::StopSound(sound_id))
-                                        .expect("sound channel error");
-                                    error!("get_local_path_from_sound_config failed unknown error")
-                                }
-                                Err(err) => {
-                                    gui_sender_clone
-                                        .send(Message::StopSound(sound_id))
-                                        .expect("sound channel error");
-                                    error!("get_local_path_from_sound_config failed: {:#}", err)
-                                }
-                            },
-                        );
                     }
                 }
-                Message::_PlaySoundDownloaded(sound_id, sound_devices, path) => {
+                Message::ClearQueue(sound_devices) => {
+                    GLOBAL_QUEUE.write().remove(&sound_devices);
+                }
+                Message::MoveInQueue(sound_devices, from, to) => {
+                    let mut queue = GLOBAL_QUEUE.write();
+                    let queue = queue.entry(sound_devices).or_default();
+                    if from >= queue.len() || to >= queue.len() {
+                        warn!("invalid queue move from {} to {}", from, to);
+                        continue;
+                    }
+                    let sound_id = queue.remove(from).unwrap();
+                    queue.insert(to, sound_id);
+                }
+                Message::RemoveFromQueue(sound_devices, index) => {
+                    if let Some(queue) = GLOBAL_QUEUE.write().get_mut(&sound_devices) {
+                        queue.remove(index);
+                    }
+                }
+                Message::SetInputProcessor(processor, enabled) => {
+                    INPUT_PROCESSORS_ENABLED[processor as usize].store(enabled, Ordering::Relaxed);
+                }
+                Message::SetVoiceEffect(effect) => {
+                    set_voice_effect(effect);
+                }
+                Message::_PlaySoundDownloaded(
+                    sound_id,
+                    sound_devices,
+                    path,
+                    loudness_gain,
+                    reversed,
+                ) => {
                     let sound = {
                         let sound = soundboards::find_sound(sound_id);
                         if sound.is_none() {
                             error!("unknown sound_id");
+                            remove_pending_sound(&mut sinks, sound_id);
                             continue;
                         }
                         sound.unwrap()
                     };
-                    if sound_devices == SoundDevices::Both || sound_devices == SoundDevices::Output
-                    {
+                    if sound_devices.includes_output() {
                         match insert_sink_with_config(
                             &path,
-                            output_device.clone(),
-                            &mut output_sink,
+                            devices.output_device.clone(),
+                            &mut devices.output_sink,
                             &sound,
+                            loudness_gain,
+                            reversed.as_ref(),
                             &mut sinks,
                         ) {
                             Ok(path) => path,
                             Err(err) => {
                                 error!("failed to insert sound at output sink {}", err);
+                                remove_pending_sound(&mut sinks, sound_id);
                                 continue;
                             }
                         };
                     }
-                    if sound_devices == SoundDevices::Both || sound_devices == SoundDevices::Loop {
-                        match insert_sink_with_config(
+                    for (name, loop_device, loopback_sink) in devices.loopback_sinks.iter_mut() {
+                        if !sound_devices.includes_loop(name) {
+                            continue;
+                        }
+                        if let Err(err) = insert_sink_with_config(
                             &path,
                             Some(loop_device.clone()),
-                            &mut loopback_sink,
+                            loopback_sink,
                             &sound,
+                            loudness_gain,
+                            reversed.as_ref(),
                             &mut sinks,
                         ) {
-                            Ok(path) => path,
-                            Err(err) => {
-                                error!("failed to insert sound at loop sink {}", err);
-                                continue;
-                            }
-                        };
+                            error!("failed to insert sound at loop sink {} {}", name, err);
+                        }
                     }
+                    remove_pending_sound(&mut sinks, sound_id);
                 }
                 Message::StopSound(sound_id) => {
                     if let Some((_, _, _)) = sinks.remove(&sound_id) {
-                        output_sink.remove(&sound_id);
-                        loopback_sink.remove(&sound_id);
+                        devices.output_sink.remove(&sound_id);
+                        for (_, _, loopback_sink) in devices.loopback_sinks.iter_mut() {
+                            loopback_sink.remove(&sound_id);
+                        }
                     };
                 }
                 Message::StopAll => {
-                    for (key, _) in sinks.drain() {
-                        output_sink.remove(&key);
-                        loopback_sink.remove(&key);
+                    GLOBAL_QUEUE.write().clear();
+                    stop_all_sounds(&mut sinks, &mut devices);
+                }
+                Message::PauseSound(sound_id) => {
+                    if let Some((status, _, _)) = sinks.get_mut(&sound_id) {
+                        if *status == SoundStatus::Playing {
+                            *status = SoundStatus::Paused;
+                            devices.output_sink.set_paused(&sound_id, true);
+                            for (_, _, loopback_sink) in devices.loopback_sinks.iter_mut() {
+                                loopback_sink.set_paused(&sound_id, true);
+                            }
+                        }
+                    }
+                }
+                Message::ResumeSound(sound_id) => {
+                    if let Some((status, _, _)) = sinks.get_mut(&sound_id) {
+                        if *status == SoundStatus::Paused {
+                            *status = SoundStatus::Playing;
+                            devices.output_sink.set_paused(&sound_id, false);
+                            for (_, _, loopback_sink) in devices.loopback_sinks.iter_mut() {
+                                loopback_sink.set_paused(&sound_id, false);
+                            }
+                        }
                     }
                 }
-                Message::SetVolume(volume_new) => {
-                    volume = volume_new;
-                    output_sink
-                        .set_volume(volume)
-                        .expect("failed to set volume");
-                    loopback_sink
-                        .set_volume(volume)
-                        .expect("failed to set volume");
+                Message::PauseAll => {
+                    for (status, _, _) in sinks.values_mut() {
+                        if *status == SoundStatus::Playing {
+                            *status = SoundStatus::Paused;
+                        }
+                    }
+                    devices.output_sink.set_all_paused(true);
+                    for (_, _, loopback_sink) in devices.loopback_sinks.iter_mut() {
+                        loopback_sink.set_all_paused(true);
+                    }
+                }
+                Message::ResumeAll => {
+                    for (status, _, _) in sinks.values_mut() {
+                        if *status == SoundStatus::Paused {
+                            *status = SoundStatus::Playing;
+                        }
+                    }
+                    devices.output_sink.set_all_paused(false);
+                    for (_, _, loopback_sink) in devices.loopback_sinks.iter_mut() {
+                        loopback_sink.set_all_paused(false);
+                    }
+                }
+                Message::SeekSound(sound_id, position) => {
+                    if !sinks.contains_key(&sound_id) {
+                        warn!("seek for sound which is not playing {}", sound_id);
+                        continue;
+                    }
+                    if devices.output_sink.is_playing(&sound_id) {
+                        if let Err(err) = devices.output_sink.seek(&sound_id, position) {
+                            error!("failed to seek sound at output sink {:#}", err);
+                        }
+                    }
+                    for (name, _, loopback_sink) in devices.loopback_sinks.iter_mut() {
+                        if loopback_sink.is_playing(&sound_id) {
+                            if let Err(err) = loopback_sink.seek(&sound_id, position) {
+                                error!("failed to seek sound at loop sink {} {:#}", name, err);
+                            }
+                        }
+                    }
+                }
+                Message::SwitchDevices(names) => {
+                    // the old devices are used until the new devices are available
+                    match open_devices(&context, &names, &ducking_active, &input_open) {
+                        Ok(new_devices) => {
+                            devices = new_devices;
+                            devices_lost = false;
+                            if let Err(err) = devices.set_volumes(&volumes) {
+                                error!("failed to set volumes {:#}", err);
+                            }
+                        }
+                        Err(err) => {
+                            warn!("failed to switch devices, retrying {:#}", err);
+                            DEVICE_STATUS.write().error = Some(format!("{:#}", err));
+                            devices_lost = true;
+                            last_reconnect = std::time::Instant::now();
+                        }
+                    }
+                    device_names = names;
+                    if let Err(err) = app_config::save_devices(&device_names) {
+                        warn!("failed to persist devices {:#}", err);
+                    }
+                }
+                Message::SetVolume(target, volume) => {
+                    let volume = volume.max(0.0);
+                    volumes.set(target, volume);
+                    let result = match target {
+                        VolumeTarget::Output => devices.output_sink.set_volume(volume),
+                        VolumeTarget::Loopback => devices
+                            .loopback_sinks
+                            .iter()
+                            .map(|(_, _, loopback_sink)| loopback_sink.set_volume(volume))
+                            .collect(),
+                        VolumeTarget::Input => {
+                            INPUT_VOLUME.store(volume.to_bits(), Ordering::Relaxed);
+                            Ok(())
+                        }
+                    };
+                    if let Err(err) = result {
+                        error!("failed to set {:?} volume {:#}", target, err);
+                    }
                 }
                 Message::PlayStatus(_, _) => {
                     let mut sounds = Vec::new();
                     for (id, (status, instant, total_duration)) in sinks.iter() {
-                        sounds.push((*status, *id, instant.elapsed(), *total_duration));
+                        let (play_duration, loop_count) = devices
+                            .output_sink
+                            .play_status(id)
+                            .or_else(|| {
+                                devices
+                                    .loopback_sinks
+                                    .iter()
+                                    .find_map(|(_, _, loopback_sink)| loopback_sink.play_status(id))
+                            })
+                            .unwrap_or_else(|| (instant.elapsed(), 0));
+                        sounds.push((*status, *id, play_duration, *total_duration, loop_count));
                     }
                     sound_sender
-                        .send(Message::PlayStatus(sounds, volume))
+                        .send(Message::PlayStatus(sounds, volumes))
                         .expect("sound channel error");
                 }
             },
+            Err(crossbeam_channel::RecvTimeoutError::Timeout) => {}
             Err(err) => {
                 error!("message receive error {}", err);
             }
         };
         sinks.retain(|key, (status, _, _)| {
             *status == SoundStatus::Downloading
-                || output_sink.is_playing(&key)
-                || loopback_sink.is_playing(&key)
+                || devices.output_sink.is_playing(&key)
+                || devices
+                    .loopback_sinks
+                    .iter_mut()
+                    .any(|(_, _, loopback_sink)| loopback_sink.is_playing(&key))
         });
-        if loopback_sink.stopped() {
-            loopback_sink
-                .start()
-                .expect("failed to start loopback_sink again");
-        }
-        if output_sink.stopped() {
-            output_sink
-                .start()
-                .expect("failed to start output_sink again");
-        }
-        if let Some(loopback_device) = loopback_device.as_ref() {
-            if !loopback_device.is_started() {
-                loopback_device
-                    .start()
-                    .expect("failed to start loopback device again");
+        ducking_active.store(
+            sinks.iter().any(|(key, (status, _, _))| {
+                *status == SoundStatus::Playing
+                    && devices
+                        .primary_loop_sink()
+                        .map_or(false, |sink| sink.is_playing(&key))
+            }),
+            Ordering::Relaxed,
+        );
+        for (sound_devices, queue) in GLOBAL_QUEUE.write().iter_mut() {
+            if let Some(sound_id) = queue_current.get(sound_devices) {
+                if sinks.contains_key(sound_id) {
+                    continue;
+                }
+            }
+            queue_current.remove(sound_devices);
+            while let Some(sound_id) = queue.pop_front() {
+                match start_sound(sound_id, sound_devices.clone(), &mut sinks, &gui_sender) {
+                    Ok(()) => {
+                        queue_current.insert(sound_devices.clone(), sound_id);
+                        break;
+                    }
+                    Err(err) => error!("failed to play queued sound {:#}", err),
+                }
+            }
+        }
+        // reopen all devices once the lost devices are available again
+        if !devices_lost && devices.lost() {
+            warn!("lost audio device, reconnecting");
+            devices_lost = true;
+        }
+        // the devices are only reopened for missing loop devices once all of them are available
+        if !devices_lost
+            && !devices.missing.is_empty()
+            && last_reconnect.elapsed() >= RECONNECT_INTERVAL
+        {
+            devices_lost = find_devices(&context, &device_names).1.is_empty();
+            if !devices_lost {
+                last_reconnect = std::time::Instant::now();
+            }
+        }
+        if devices_lost && last_reconnect.elapsed() >= RECONNECT_INTERVAL {
+            last_reconnect = std::time::Instant::now();
+            match open_devices(&context, &device_names, &ducking_active, &input_open) {
+                Ok(new_devices) => {
+                    devices = new_devices;
+                    devices_lost = false;
+                    if let Err(err) = devices.set_volumes(&volumes) {
+                        error!("failed to set volumes {:#}", err);
+                    }
+                    info!("reconnected audio devices");
+                }
+                Err(err) => trace!("failed to reconnect audio devices {:#}", err),
             }
         }
     }
@@ -544,6 +1576,8 @@ fn create_duplex_device(
     context: &Context,
     input_device: Option<miniaudio::DeviceIdAndName>,
     loop_device: miniaudio::DeviceIdAndName,
+    ducking_active: Arc<AtomicBool>,
+    input_open: Option<Arc<AtomicBool>>,
 ) -> Result<miniaudio::Device> {
     let loop_info = match context.get_device_info(
         miniaudio::DeviceType::Playback,
@@ -560,10 +1594,19 @@ fn create_duplex_device(
     let mut device_config = miniaudio::DeviceConfig::new(DeviceType::Duplex);
     let format = loop_info.formats()[0];
     info!("duplex: format {:?}", format);
-    device_config.capture_mut().set_format(format);
+    let config = app_config::get_app_config();
+    let mic_ducking = config.mic_ducking.unwrap_or_default();
+    // processing needs float samples, miniaudio converts from and to the device format
+    device_config
+        .capture_mut()
+        .set_format(miniaudio::Format::F32);
+    device_config
+        .playback_mut()
+        .set_format(miniaudio::Format::F32);
     let channels = loop_info.max_channels();
     info!("duplex: channels {}", channels);
     device_config.capture_mut().set_channels(channels);
+    device_config.playback_mut().set_channels(channels);
     if let Some(input_device) = input_device {
         device_config
             .capture_mut()
@@ -587,8 +1630,61 @@ fn create_duplex_device(
         .playback_mut()
         .set_device_id(Some(loop_device.id().clone()));
 
+    let mut ducker = if mic_ducking {
+        Some(ducking::Ducker::new(
+            ducking_active,
+            config.ducking_amount.unwrap_or_default(),
+            config.ducking_attack.unwrap_or_default(),
+            config.ducking_release.unwrap_or_default(),
+            sample_rate,
+        ))
+    } else {
+        None
+    };
+
+    let mut input_gate =
+        input_open.map(|input_open| push_to_talk::InputGate::new(input_open, sample_rate));
+
+    let mut processors = BufferedSource::new(channels as u16, sample_rate)
+        .noise_gate(config.noise_gate_threshold.unwrap_or_default())
+        .high_pass(config.high_pass_frequency.unwrap_or_default())
+        .compressor(
+            config.compressor_threshold.unwrap_or_default(),
+            config.compressor_ratio.unwrap_or_default(),
+        );
+
+    let mut voice_effects = effects::VoiceEffects::new(
+        channels as usize,
+        sample_rate,
+        config.pitch_shift.unwrap_or_default(),
+    );
+    let mut input_buffer: Vec<f32> = Vec::new();
+
     device_config.set_data_callback(move |_device, output, input| {
-        output.as_bytes_mut().copy_from_slice(input.as_bytes());
+        input_buffer.clear();
+        input_buffer.extend_from_slice(input.as_samples::<f32>());
+        // before the processors so the limiter catches the echo and reverb tails
+        voice_effects.process(&mut input_buffer, get_voice_effect());
+
+        let enabled = &*INPUT_PROCESSORS_ENABLED;
+        processors
+            .set_enabled(enabled[InputProcessor::Compressor as usize].load(Ordering::Relaxed));
+        let high_pass = processors.inner_mut();
+        high_pass.set_enabled(enabled[InputProcessor::HighPass as usize].load(Ordering::Relaxed));
+        let noise_gate = high_pass.inner_mut();
+        noise_gate.set_enabled(enabled[InputProcessor::NoiseGate as usize].load(Ordering::Relaxed));
+        noise_gate.inner_mut().push(&input_buffer);
+
+        let input_volume = f32::from_bits(INPUT_VOLUME.load(Ordering::Relaxed));
+        for sample in output.as_samples_mut::<f32>() {
+            *sample = processors.next().unwrap_or(0.0) * input_volume;
+        }
+        if let Some(input_gate) = input_gate.as_mut() {
+            input_gate.process(output.as_samples_mut::<f32>(), channels as usize);
+        }
+        if let Some(ducker) = ducker.as_mut() {
+            ducker.process(output.as_samples_mut::<f32>(), channels as usize);
+        }
     });
 
     device_config.set_stop_callback(|_device| {
@@ -596,8 +1692,10 @@ fn create_duplex_device(
     });
 
     let device = miniaudio::Device::new(Some(context.clone()), &device_config)
-        .expect("failed to open playback device");
-    device.start().expect("failed to start device");
+        .map_err(|err| anyhow!("failed to open duplex device {}", err))?;
+    device
+        .start()
+        .map_err(|err| anyhow!("failed to start duplex device {}", err))?;
 
     Ok(device)
 }
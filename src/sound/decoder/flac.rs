@@ -1,4 +1,5 @@
 // Initial version from Rodio APACHE LICENSE 2.0
+use anyhow::{anyhow, Result};
 use std::io::{Read, Seek, SeekFrom};
 use std::mem;
 use std::time::Duration;
@@ -12,10 +13,15 @@ pub struct FlacDecoder<R>
 where
     R: Read + Seek,
 {
-    reader: FlacReader<R>,
+    // claxon only reads forward so seeking backwards requires a new reader
+    reader: Option<FlacReader<R>>,
+    /// Stream position of the flac data to start over
+    stream_start: u64,
     current_block: Vec<i32>,
     current_block_channel_len: usize,
     current_block_off: usize,
+    /// Inter-channel sample index of the first sample in the current block
+    current_block_time: u64,
     bits_per_sample: u32,
     sample_rate: u32,
     channels: u16,
@@ -32,16 +38,19 @@ where
             return Err(data);
         }
 
+        let stream_start = data.seek(SeekFrom::Current(0)).unwrap();
         let reader = FlacReader::new(data).unwrap();
         let spec = reader.streaminfo();
 
         Ok(FlacDecoder {
-            reader,
+            reader: Some(reader),
+            stream_start,
             current_block: Vec::with_capacity(
                 spec.max_block_size as usize * spec.channels as usize,
             ),
             current_block_channel_len: 1,
             current_block_off: 0,
+            current_block_time: 0,
             bits_per_sample: spec.bits_per_sample,
             sample_rate: spec.sample_rate,
             channels: spec.channels as u16,
@@ -76,6 +85,62 @@ where
         self.samples
             .map(|s| Duration::from_micros(s * 1_000_000 / self.sample_rate as u64))
     }
+
+    /// Seeks by decoding the blocks up to the position
+    fn seek(&mut self, position: Duration) -> Result<()> {
+        let target = (position.as_secs_f64() * self.sample_rate as f64) as u64;
+        if target < self.current_block_time {
+            let mut data = self
+                .reader
+                .take()
+                .ok_or_else(|| anyhow!("flac seek failed: reader failed before"))?
+                .into_inner();
+            data.seek(SeekFrom::Start(self.stream_start))?;
+            self.reader =
+                Some(FlacReader::new(data).map_err(|err| anyhow!("flac seek failed: {}", err))?);
+            self.current_block.clear();
+            self.current_block_channel_len = 1;
+            self.current_block_off = 0;
+            self.current_block_time = 0;
+        }
+
+        loop {
+            let block_frames = (self.current_block.len() / self.channels as usize) as u64;
+            if target < self.current_block_time + block_frames {
+                self.current_block_off =
+                    (target - self.current_block_time) as usize * self.channels as usize;
+                return Ok(());
+            }
+            if !self.read_next_block() {
+                self.current_block_off = self.current_block.len();
+                return Ok(());
+            }
+        }
+    }
+}
+
+impl<R> FlacDecoder<R>
+where
+    R: Read + Seek,
+{
+    /// Reads the next block, returns false at the end of the stream
+    fn read_next_block(&mut self) -> bool {
+        let reader = match self.reader.as_mut() {
+            Some(reader) => reader,
+            None => return false,
+        };
+        let buffer = mem::replace(&mut self.current_block, Vec::new());
+        match reader.blocks().read_next_or_eof(buffer) {
+            Ok(Some(block)) => {
+                self.current_block_channel_len = (block.len() / block.channels()) as usize;
+                self.current_block_time = block.time();
+                self.current_block = block.into_buffer();
+                self.current_block_off = 0;
+                true
+            }
+            _ => false,
+        }
+    }
 }
 
 impl<R> Iterator for FlacDecoder<R>
@@ -103,14 +168,8 @@ where
                 return Some(real_val as i16);
             }
 
-            let buffer = mem::replace(&mut self.current_block, Vec::new());
-            match self.reader.blocks().read_next_or_eof(buffer) {
-                Ok(Some(block)) => {
-                    self.current_block_channel_len = (block.len() / block.channels()) as usize;
-                    self.current_block = block.into_buffer();
-                    self.current_block_off = 0;
-                }
-                _ => return None,
+            if !self.read_next_block() {
+                return None;
             }
         }
     }
@@ -321,6 +321,24 @@ where
             DecoderImpl::XM(ref source) => source.total_duration(),
         }
     }
+
+    #[inline]
+    fn seek(&mut self, position: Duration) -> anyhow::Result<()> {
+        match self.0 {
+            #[cfg(feature = "wav")]
+            DecoderImpl::Wav(ref mut source) => source.seek(position),
+            #[cfg(feature = "vorbis")]
+            DecoderImpl::Vorbis(ref mut source) => source.seek(position),
+            #[cfg(feature = "opus")]
+            DecoderImpl::Opus(ref mut source) => source.seek(position),
+            #[cfg(feature = "flac")]
+            DecoderImpl::Flac(ref mut source) => source.seek(position),
+            #[cfg(feature = "mp3")]
+            DecoderImpl::Mp3(ref mut source) => source.seek(position),
+            #[cfg(feature = "xm")]
+            DecoderImpl::XM(ref mut source) => source.seek(position),
+        }
+    }
 }
 
 /// Error that can happen when creating a decoder.
@@ -1,5 +1,6 @@
 // Initial version from Rodio APACHE LICENSE 2.0
 use crate::sound::source::Source;
+use anyhow::{anyhow, Result};
 use log::{error, info, trace, warn};
 use minimp3::{Decoder, Frame};
 use std::io::SeekFrom;
@@ -10,9 +11,165 @@ pub struct Mp3Decoder<R>
 where
     R: Read + Seek,
 {
-    decoder: Decoder<R>,
+    // minimp3 buffers the reader internally so seeking requires a new decoder
+    decoder: Option<Decoder<R>>,
     current_frame: Frame,
     current_frame_offset: usize,
+    /// Byte offset of the first frame after an ID3v2 tag
+    data_start: u64,
+    /// Read on the first seek
+    frame_index: Option<FrameIndex>,
+}
+
+/// Byte offsets and start times of the frames of a stream
+struct FrameIndex {
+    /// Byte offset and start time in samples per channel of each frame
+    frames: Vec<(u64, u64)>,
+    /// Byte offset after the last frame
+    end: u64,
+    /// Samples per channel of all frames
+    samples: u64,
+    sample_rate: u32,
+}
+
+impl FrameIndex {
+    /// Reads the headers of all frames from `data_start` without decoding them
+    ///
+    /// The index ends at the first invalid or truncated frame.
+    fn read<R>(data: &mut R, data_start: u64) -> Result<Self>
+    where
+        R: Read + Seek,
+    {
+        data.seek(SeekFrom::Start(data_start))?;
+        let mut index = FrameIndex {
+            frames: Vec::new(),
+            end: data_start,
+            samples: 0,
+            sample_rate: 0,
+        };
+        let mut header = [0; 4];
+        while data.read_exact(&mut header).is_ok() {
+            let (length, frame_samples, sample_rate) = match frame_header_info(header) {
+                Some(info) => info,
+                None => break,
+            };
+            let skipped = std::io::copy(
+                &mut Read::take(&mut *data, length - 4),
+                &mut std::io::sink(),
+            )?;
+            if skipped < length - 4 {
+                break;
+            }
+            if index.frames.is_empty() {
+                index.sample_rate = sample_rate;
+            }
+            index.frames.push((index.end, index.samples));
+            index.end += length;
+            index.samples += frame_samples;
+        }
+        if index.frames.is_empty() {
+            return Err(anyhow!("no mp3 frame found"));
+        }
+        Ok(index)
+    }
+
+    /// Returns the byte offset of the frame containing the sample and the samples to skip in it
+    fn find(&self, sample: u64) -> (u64, u64) {
+        if sample >= self.samples {
+            return (self.end, 0);
+        }
+        let frame = match self
+            .frames
+            .binary_search_by_key(&sample, |(_, start)| *start)
+        {
+            Ok(frame) => frame,
+            Err(frame) => frame - 1,
+        };
+        let (offset, start) = self.frames[frame];
+        (offset, sample - start)
+    }
+}
+
+/// Returns the byte length, the samples per channel and the sample rate of a frame
+fn frame_header_info(header: [u8; 4]) -> Option<(u64, u64, u32)> {
+    const BITRATES_V1_L1: [u64; 15] = [
+        0, 32, 64, 96, 128, 160, 192, 224, 256, 288, 320, 352, 384, 416, 448,
+    ];
+    const BITRATES_V1_L2: [u64; 15] = [
+        0, 32, 48, 56, 64, 80, 96, 112, 128, 160, 192, 224, 256, 320, 384,
+    ];
+    const BITRATES_V1_L3: [u64; 15] = [
+        0, 32, 40, 48, 56, 64, 80, 96, 112, 128, 160, 192, 224, 256, 320,
+    ];
+    const BITRATES_V2_L1: [u64; 15] = [
+        0, 32, 48, 56, 64, 80, 96, 112, 128, 144, 160, 176, 192, 224, 256,
+    ];
+    const BITRATES_V2_L23: [u64; 15] =
+        [0, 8, 16, 24, 32, 40, 48, 56, 64, 80, 96, 112, 128, 144, 160];
+
+    let header = u32::from_be_bytes(header);
+    if header >> 21 != 0x7ff {
+        return None;
+    }
+    // version 3 is MPEG 1, 2 is MPEG 2 and 0 is MPEG 2.5
+    let version = (header >> 19) & 0b11;
+    // layer 3 is layer I, 2 is layer II and 1 is layer III
+    let layer = (header >> 17) & 0b11;
+    let bitrate_index = ((header >> 12) & 0xf) as usize;
+    let sample_rate_index = ((header >> 10) & 0b11) as usize;
+    let padding = ((header >> 9) & 1) as u64;
+    // free format bitrates are not supported
+    if version == 1
+        || layer == 0
+        || bitrate_index == 0
+        || bitrate_index == 15
+        || sample_rate_index == 3
+    {
+        return None;
+    }
+
+    let sample_rate = [44100, 48000, 32000][sample_rate_index] >> (3 - version.max(1));
+    let bitrates = match (version, layer) {
+        (3, 3) => &BITRATES_V1_L1,
+        (3, 2) => &BITRATES_V1_L2,
+        (3, _) => &BITRATES_V1_L3,
+        (_, 3) => &BITRATES_V2_L1,
+        _ => &BITRATES_V2_L23,
+    };
+    let bitrate = bitrates[bitrate_index] * 1000;
+    let samples = match (version, layer) {
+        (_, 3) => 384,
+        (3, _) | (_, 2) => 1152,
+        _ => 576,
+    };
+    let length = if layer == 3 {
+        (12 * bitrate / sample_rate as u64 + padding) * 4
+    } else {
+        samples / 8 * bitrate / sample_rate as u64 + padding
+    };
+    Some((length, samples, sample_rate))
+}
+
+/// Returns the stream position after an ID3v2 tag, then resets it to where it was.
+fn id3v2_end<R>(mut data: R) -> u64
+where
+    R: Read + Seek,
+{
+    let stream_pos = data.seek(SeekFrom::Current(0)).unwrap();
+
+    let mut header = [0; 10];
+    let mut end = stream_pos;
+    if data.read_exact(&mut header).is_ok() && &header[0..3] == b"ID3" {
+        // the tag size is a 28 bit synchsafe integer without the header and footer
+        let size = header[6..10]
+            .iter()
+            .fold(0u64, |size, byte| (size << 7) | (*byte & 0x7f) as u64);
+        let footer = if header[5] & 0x10 != 0 { 10 } else { 0 };
+        end += 10 + size + footer;
+    }
+
+    data.seek(SeekFrom::Start(stream_pos)).unwrap();
+    end
 }
 
 fn is_mp3<R>(mut data: R) -> bool
@@ -45,13 +202,16 @@ where
         if !is_mp3(data.by_ref()) {
             return Err(data);
         }
+        let data_start = id3v2_end(data.by_ref());
         let mut decoder = Decoder::new(data);
         let current_frame = decoder.next_frame().unwrap();
 
         Ok(Mp3Decoder {
-            decoder,
+            decoder: Some(decoder),
             current_frame,
             current_frame_offset: 0,
+            data_start,
+            frame_index: None,
         })
     }
 
@@ -121,6 +281,52 @@ where
     fn total_duration(&self) -> Option<Duration> {
         None
     }
+
+    /// Seeks to the frame containing the position
+    ///
+    /// The frame headers are read on the first seek which makes seeking exact for constant
+    /// and variable bitrate files.
+    fn seek(&mut self, position: Duration) -> Result<()> {
+        let mut reader = self
+            .decoder
+            .take()
+            .ok_or_else(|| anyhow!("mp3 seek failed: decoder failed before"))?
+            .into_inner();
+        if self.frame_index.is_none() {
+            let stream_pos = reader.seek(SeekFrom::Current(0));
+            match FrameIndex::read(&mut reader, self.data_start) {
+                Ok(frame_index) => self.frame_index = Some(frame_index),
+                Err(err) => {
+                    // keep decoding where the decoder stopped
+                    let seek_result = stream_pos.and_then(|pos| reader.seek(SeekFrom::Start(pos)));
+                    self.decoder = Some(Decoder::new(reader));
+                    seek_result?;
+                    return Err(anyhow!("mp3 seek failed: {}", err));
+                }
+            }
+        }
+        let frame_index = self.frame_index.as_ref().unwrap();
+        let sample = (position.as_secs_f64() * frame_index.sample_rate as f64) as u64;
+        let (byte_offset, skip) = frame_index.find(sample);
+
+        let seek_result = reader.seek(SeekFrom::Start(byte_offset));
+        let mut decoder = Decoder::new(reader);
+        let next_frame = decoder.next_frame();
+        self.decoder = Some(decoder);
+        seek_result?;
+
+        match next_frame {
+            Ok(frame) => {
+                self.current_frame_offset = (skip as usize * frame.channels).min(frame.data.len());
+                self.current_frame = frame;
+            }
+            Err(minimp3::Error::Eof) => {
+                self.current_frame_offset = self.current_frame.data.len();
+            }
+            Err(err) => return Err(anyhow!("mp3 seek failed: {}", err)),
+        }
+        Ok(())
+    }
 }
 
 impl<R> Iterator for Mp3Decoder<R>
@@ -132,7 +338,7 @@ where
     #[inline]
     fn next(&mut self) -> Option<i16> {
         if self.current_frame_offset == self.current_frame.data.len() {
-            match self.decoder.next_frame() {
+            match self.decoder.as_mut()?.next_frame() {
                 Ok(frame) => self.current_frame = frame,
                 _ => return None,
             }
@@ -1,4 +1,5 @@
 // Initial version from Rodio APACHE LICENSE 2.0
+use anyhow::{anyhow, Result};
 use log::{error, info, trace, warn};
 use std::io::{Read, Seek, SeekFrom};
 use std::time::Duration;
@@ -114,6 +115,27 @@ where
     fn total_duration(&self) -> Option<Duration> {
         None
     }
+
+    /// Seeks to the ogg page containing the position, the pre-skip is ignored
+    #[inline]
+    fn seek(&mut self, position: Duration) -> Result<()> {
+        let granule_position = (position.as_secs_f64() * SAMPLE_RATE as f64) as u64;
+        let found = self
+            .packet_reader
+            .seek_absgp(None, granule_position)
+            .map_err(|err| anyhow!("opus seek failed: {}", err))?;
+        if !found {
+            return Err(anyhow!("opus seek position is after the end"));
+        }
+        // the decoder state belongs to the previous position
+        *self.decoder.lock() = Decoder::new(
+            audiopus::SampleRate::try_from(SAMPLE_RATE as i32).unwrap(),
+            audiopus::Channels::try_from(CHANNELS as i32).unwrap(),
+        )
+        .map_err(|err| anyhow!("failed to create opus decoder: {}", err))?;
+        self.current_data = Vec::new().into_iter();
+        Ok(())
+    }
 }
 
 impl<R> Iterator for OpusDecoder<R>
@@ -1,4 +1,5 @@
 // Initial version from Rodio APACHE LICENSE 2.0
+use anyhow::{anyhow, Result};
 use log::{error, info, trace, warn};
 use std::io::{Read, Seek, SeekFrom};
 use std::time::Duration;
@@ -87,6 +88,17 @@ where
     fn total_duration(&self) -> Option<Duration> {
         None
     }
+
+    /// Seeks to the ogg page containing the position
+    #[inline]
+    fn seek(&mut self, position: Duration) -> Result<()> {
+        let granule_position = (position.as_secs_f64() * self.sample_rate() as f64) as u64;
+        self.stream_reader
+            .seek_absgp_pg(granule_position)
+            .map_err(|err| anyhow!("vorbis seek failed: {}", err))?;
+        self.current_data = Vec::new().into_iter();
+        Ok(())
+    }
 }
 
 impl<R> Iterator for VorbisDecoder<R>
@@ -110,13 +122,17 @@ where
             }
             Some(sample)
         } else {
-            if let Some(data) = self
+            // packets can be empty e.g. the first one after seeking
+            while let Some(data) = self
                 .stream_reader
                 .read_dec_packet_itl()
                 .ok()
                 .and_then(|v| v)
             {
                 self.current_data = data.into_iter();
+                if self.current_data.len() > 0 {
+                    break;
+                }
             }
             self.current_data.next()
         }
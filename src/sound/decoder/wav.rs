@@ -1,4 +1,5 @@
 // Initial version from Rodio APACHE LICENSE 2.0
+use anyhow::Result;
 use std::io::{Read, Seek, SeekFrom};
 use std::time::Duration;
 
@@ -111,6 +112,16 @@ where
         let ms = self.len() * 1000 / (self.channels as usize * self.sample_rate as usize);
         Some(Duration::from_millis(ms as u64))
     }
+
+    #[inline]
+    fn seek(&mut self, position: Duration) -> Result<()> {
+        let reader = &mut self.reader;
+        let frame = ((position.as_secs_f64() * self.sample_rate as f64) as u32)
+            .min(reader.reader.duration());
+        reader.reader.seek(frame)?;
+        reader.samples_read = frame * self.channels as u32;
+        Ok(())
+    }
 }
 
 impl<R> Iterator for WavDecoder<R>
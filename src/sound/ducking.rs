@@ -0,0 +1,56 @@
+//! Ducking of the microphone passthrough while sounds are playing
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+use super::source::smoothing_coeff;
+
+/// Attenuates samples while the shared `active` flag is set
+///
+/// The gain follows the target with separate attack and release time constants to avoid clicks
+pub struct Ducker {
+    active: Arc<AtomicBool>,
+    ducked_gain: f32,
+    attack_coeff: f32,
+    release_coeff: f32,
+    gain: f32,
+}
+
+impl Ducker {
+    /// Creates a ducker which attenuates by amount in dB
+    pub fn new(
+        active: Arc<AtomicBool>,
+        amount: f32,
+        attack: f32,
+        release: f32,
+        sample_rate: u32,
+    ) -> Self {
+        Self {
+            active,
+            ducked_gain: 10f32.powf(-amount.abs() / 20.0),
+            attack_coeff: smoothing_coeff(attack, sample_rate),
+            release_coeff: smoothing_coeff(release, sample_rate),
+            gain: 1.0,
+        }
+    }
+
+    /// Applies the ducking gain to interleaved samples
+    pub fn process(&mut self, samples: &mut [f32], channels: usize) {
+        let (target, coeff) = if self.active.load(Ordering::Relaxed) {
+            (self.ducked_gain, self.attack_coeff)
+        } else {
+            (1.0, self.release_coeff)
+        };
+        if (self.gain - target).abs() < 1e-4 {
+            self.gain = target;
+            if target < 1.0 {
+                samples.iter_mut().for_each(|sample| *sample *= target);
+            }
+            return;
+        }
+        for frame in samples.chunks_mut(channels.max(1)) {
+            self.gain += (target - self.gain) * coeff;
+            frame.iter_mut().for_each(|sample| *sample *= self.gain);
+        }
+    }
+}
@@ -0,0 +1,263 @@
+//! Real-time voice effects for the microphone passthrough
+
+use super::VoiceEffect;
+
+/// Length of the grains of the pitch shifter in seconds
+const PITCH_SHIFT_WINDOW: f32 = 0.05;
+/// Frequency of the ring modulator in Hz
+const RING_MODULATOR_FREQUENCY: f32 = 50.0;
+/// Comb and allpass delays of the reverb in samples at 44.1kHz (Freeverb tuning)
+const REVERB_COMB_DELAYS: [usize; 4] = [1116, 1188, 1277, 1356];
+const REVERB_ALLPASS_DELAYS: [usize; 2] = [556, 441];
+const REVERB_FEEDBACK: f32 = 0.84;
+const REVERB_DAMPING: f32 = 0.2;
+const REVERB_MIX: f32 = 0.3;
+const ECHO_DELAY: f32 = 0.35;
+const ECHO_FEEDBACK: f32 = 0.4;
+const ECHO_MIX: f32 = 0.5;
+
+/// Circular delay line
+struct DelayLine {
+    buffer: Vec<f32>,
+    position: usize,
+}
+
+impl DelayLine {
+    fn new(length: usize) -> Self {
+        Self {
+            buffer: vec![0.0; length.max(1)],
+            position: 0,
+        }
+    }
+
+    /// Returns the oldest sample
+    #[inline]
+    fn read(&self) -> f32 {
+        self.buffer[self.position]
+    }
+
+    /// Returns the sample written delay samples ago with linear interpolation
+    #[inline]
+    fn read_delayed(&self, delay: f32) -> f32 {
+        let len = self.buffer.len();
+        let position = self.position as f32 + len as f32 - delay;
+        let index = position as usize;
+        let frac = position.fract();
+        let a = self.buffer[index % len];
+        let b = self.buffer[(index + 1) % len];
+        a + (b - a) * frac
+    }
+
+    /// Replaces the oldest sample and advances
+    #[inline]
+    fn write(&mut self, sample: f32) {
+        self.buffer[self.position] = sample;
+        self.position = (self.position + 1) % self.buffer.len();
+    }
+}
+
+/// Delay line based pitch shifter with two crossfaded read heads
+pub struct PitchShifter {
+    delay: DelayLine,
+    window: f32,
+    phase: f32,
+}
+
+impl PitchShifter {
+    pub fn new(sample_rate: u32) -> Self {
+        let window = PITCH_SHIFT_WINDOW * sample_rate as f32;
+        Self {
+            delay: DelayLine::new(window as usize + 3),
+            window,
+            phase: 0.0,
+        }
+    }
+
+    /// Shifts the pitch by the frequency ratio
+    #[inline]
+    pub fn process(&mut self, sample: f32, ratio: f32) -> f32 {
+        self.delay.write(sample);
+        let mut output = 0.0;
+        for offset in &[0.0, 0.5] {
+            let phase = (self.phase + offset) % 1.0;
+            // hann windows of both heads sum to one
+            let gain = (std::f32::consts::PI * phase).sin().powi(2);
+            output += self.delay.read_delayed(1.0 + phase * self.window) * gain;
+        }
+        self.phase = (self.phase + (1.0 - ratio) / self.window).rem_euclid(1.0);
+        output
+    }
+}
+
+struct Comb {
+    delay: DelayLine,
+    filter_store: f32,
+}
+
+impl Comb {
+    #[inline]
+    fn process(&mut self, sample: f32) -> f32 {
+        let output = self.delay.read();
+        self.filter_store = output * (1.0 - REVERB_DAMPING) + self.filter_store * REVERB_DAMPING;
+        self.delay
+            .write(sample + self.filter_store * REVERB_FEEDBACK);
+        output
+    }
+}
+
+struct Allpass {
+    delay: DelayLine,
+}
+
+impl Allpass {
+    #[inline]
+    fn process(&mut self, sample: f32) -> f32 {
+        let delayed = self.delay.read();
+        self.delay.write(sample + delayed * 0.5);
+        delayed - sample
+    }
+}
+
+/// Simplified Freeverb with parallel combs followed by allpasses
+struct Reverb {
+    combs: Vec<Comb>,
+    allpasses: Vec<Allpass>,
+}
+
+impl Reverb {
+    fn new(sample_rate: u32) -> Self {
+        let scale = |delay: usize| delay * sample_rate as usize / 44100;
+        Self {
+            combs: REVERB_COMB_DELAYS
+                .iter()
+                .map(|delay| Comb {
+                    delay: DelayLine::new(scale(*delay)),
+                    filter_store: 0.0,
+                })
+                .collect(),
+            allpasses: REVERB_ALLPASS_DELAYS
+                .iter()
+                .map(|delay| Allpass {
+                    delay: DelayLine::new(scale(*delay)),
+                })
+                .collect(),
+        }
+    }
+
+    #[inline]
+    fn process(&mut self, sample: f32) -> f32 {
+        let input = sample * 0.1;
+        let mut wet: f32 = self.combs.iter_mut().map(|comb| comb.process(input)).sum();
+        for allpass in self.allpasses.iter_mut() {
+            wet = allpass.process(wet);
+        }
+        sample * (1.0 - REVERB_MIX) + wet * REVERB_MIX
+    }
+}
+
+struct Echo {
+    delay: DelayLine,
+}
+
+impl Echo {
+    #[inline]
+    fn process(&mut self, sample: f32) -> f32 {
+        let delayed = self.delay.read();
+        self.delay.write(sample + delayed * ECHO_FEEDBACK);
+        sample + delayed * ECHO_MIX
+    }
+}
+
+/// State of the selected effect for every channel
+enum EffectState {
+    PitchShift(Vec<PitchShifter>, f32),
+    Robot(f32),
+    Reverb(Vec<Reverb>),
+    Echo(Vec<Echo>),
+}
+
+/// Applies the selected voice effect to interleaved samples
+pub struct VoiceEffects {
+    channels: usize,
+    sample_rate: u32,
+    pitch_shift_ratio: f32,
+    effect: Option<VoiceEffect>,
+    state: Option<EffectState>,
+}
+
+impl VoiceEffects {
+    /// Creates the effects, the pitch shift is specified in semitones
+    pub fn new(channels: usize, sample_rate: u32, pitch_shift: f32) -> Self {
+        Self {
+            channels: channels.max(1),
+            sample_rate,
+            pitch_shift_ratio: 2f32.powf(pitch_shift / 12.0),
+            effect: None,
+            state: None,
+        }
+    }
+
+    fn create_state(&self, effect: VoiceEffect) -> EffectState {
+        let channels = 0..self.channels;
+        match effect {
+            VoiceEffect::PitchShift => EffectState::PitchShift(
+                channels
+                    .map(|_| PitchShifter::new(self.sample_rate))
+                    .collect(),
+                self.pitch_shift_ratio,
+            ),
+            VoiceEffect::Robot => EffectState::Robot(0.0),
+            VoiceEffect::Reverb => {
+                EffectState::Reverb(channels.map(|_| Reverb::new(self.sample_rate)).collect())
+            }
+            VoiceEffect::Echo => EffectState::Echo(
+                channels
+                    .map(|_| Echo {
+                        delay: DelayLine::new((ECHO_DELAY * self.sample_rate as f32) as usize),
+                    })
+                    .collect(),
+            ),
+        }
+    }
+
+    pub fn process(&mut self, samples: &mut [f32], effect: Option<VoiceEffect>) {
+        // start with empty delay lines so old tails do not resurface
+        if effect != self.effect {
+            self.effect = effect;
+            self.state = effect.map(|effect| self.create_state(effect));
+        }
+
+        let phase_step = RING_MODULATOR_FREQUENCY / self.sample_rate.max(1) as f32;
+        match self.state.as_mut() {
+            None => {}
+            Some(EffectState::PitchShift(shifters, ratio)) => {
+                for frame in samples.chunks_mut(self.channels) {
+                    for (sample, shifter) in frame.iter_mut().zip(shifters.iter_mut()) {
+                        *sample = shifter.process(*sample, *ratio);
+                    }
+                }
+            }
+            Some(EffectState::Robot(phase)) => {
+                for frame in samples.chunks_mut(self.channels) {
+                    let modulator = (2.0 * std::f32::consts::PI * *phase).sin();
+                    frame.iter_mut().for_each(|sample| *sample *= modulator);
+                    *phase = (*phase + phase_step) % 1.0;
+                }
+            }
+            Some(EffectState::Reverb(reverbs)) => {
+                for frame in samples.chunks_mut(self.channels) {
+                    for (sample, reverb) in frame.iter_mut().zip(reverbs.iter_mut()) {
+                        *sample = reverb.process(*sample);
+                    }
+                }
+            }
+            Some(EffectState::Echo(echos)) => {
+                for frame in samples.chunks_mut(self.channels) {
+                    for (sample, echo) in frame.iter_mut().zip(echos.iter_mut()) {
+                        *sample = echo.process(*sample);
+                    }
+                }
+            }
+        }
+    }
+}
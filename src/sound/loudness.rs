@@ -0,0 +1,303 @@
+//! Loudness measurement according to EBU R128 / ITU-R BS.1770-4
+
+use anyhow::Result;
+use log::{info, warn};
+use std::collections::VecDeque;
+use std::path::Path;
+
+use super::decoder::Decoder;
+use super::sample::Sample;
+use super::source::Source;
+use crate::download;
+
+/// Length of a gating block in sub blocks (400ms)
+const BLOCK_SUB_BLOCKS: usize = 4;
+/// Length of a sub block in seconds, gating blocks overlap by 75%
+const SUB_BLOCK_SECONDS: f64 = 0.1;
+const ABSOLUTE_GATE_LUFS: f64 = -70.0;
+const RELATIVE_GATE_LU: f64 = -10.0;
+/// Upper bound for the normalization gain to avoid blowing up noise (+12dB)
+const MAX_GAIN: f32 = 3.981;
+
+/// Second order IIR filter in direct form I
+#[derive(Debug, Clone, Copy, Default)]
+struct Biquad {
+    b0: f64,
+    b1: f64,
+    b2: f64,
+    a1: f64,
+    a2: f64,
+    x1: f64,
+    x2: f64,
+    y1: f64,
+    y2: f64,
+}
+
+impl Biquad {
+    /// High shelf of the K-weighting filter (stage 1)
+    fn k_weighting_shelf(sample_rate: u32) -> Self {
+        let f0 = 1681.974_450_955_533;
+        let gain = 3.999_843_853_973_347;
+        let q = 0.707_175_236_955_419_6;
+
+        let k = (std::f64::consts::PI * f0 / sample_rate as f64).tan();
+        let vh = 10f64.powf(gain / 20.0);
+        let vb = vh.powf(0.499_666_774_154_541_6);
+        let a0 = 1.0 + k / q + k * k;
+
+        Biquad {
+            b0: (vh + vb * k / q + k * k) / a0,
+            b1: 2.0 * (k * k - vh) / a0,
+            b2: (vh - vb * k / q + k * k) / a0,
+            a1: 2.0 * (k * k - 1.0) / a0,
+            a2: (1.0 - k / q + k * k) / a0,
+            ..Biquad::default()
+        }
+    }
+
+    /// High pass of the K-weighting filter (stage 2)
+    fn k_weighting_high_pass(sample_rate: u32) -> Self {
+        let f0 = 38.135_470_876_024_44;
+        let q = 0.500_327_037_323_877_3;
+
+        let k = (std::f64::consts::PI * f0 / sample_rate as f64).tan();
+        let a0 = 1.0 + k / q + k * k;
+
+        Biquad {
+            b0: 1.0,
+            b1: -2.0,
+            b2: 1.0,
+            a1: 2.0 * (k * k - 1.0) / a0,
+            a2: (1.0 - k / q + k * k) / a0,
+            ..Biquad::default()
+        }
+    }
+
+    #[inline]
+    fn process(&mut self, x: f64) -> f64 {
+        let y = self.b0 * x + self.b1 * self.x1 + self.b2 * self.x2
+            - self.a1 * self.y1
+            - self.a2 * self.y2;
+        self.x2 = self.x1;
+        self.x1 = x;
+        self.y2 = self.y1;
+        self.y1 = y;
+        y
+    }
+}
+
+/// Channel weighting from BS.1770, surround channels are weighted with +1.5dB
+fn channel_weight(channel: usize) -> f64 {
+    if channel < 3 {
+        1.0
+    } else {
+        1.41
+    }
+}
+
+fn power_to_loudness(power: f64) -> f64 {
+    -0.691 + 10.0 * power.log10()
+}
+
+/// Measures the integrated loudness of the source in LUFS
+///
+/// Returns `None` if the source is shorter than one gating block or silent
+pub fn integrated_loudness<S>(mut source: S) -> Option<f32>
+where
+    S: Source,
+    S::Item: Sample,
+{
+    let channels = source.channels() as usize;
+    let sample_rate = source.sample_rate();
+    if channels == 0 || sample_rate == 0 {
+        return None;
+    }
+
+    let mut filters: Vec<(Biquad, Biquad)> = (0..channels)
+        .map(|_| {
+            (
+                Biquad::k_weighting_shelf(sample_rate),
+                Biquad::k_weighting_high_pass(sample_rate),
+            )
+        })
+        .collect();
+
+    let sub_block_frames = (sample_rate as f64 * SUB_BLOCK_SECONDS) as usize;
+    let mut sub_block_power = 0.0;
+    let mut sub_block_frame = 0;
+    let mut channel = 0;
+    let mut sub_blocks: VecDeque<f64> = VecDeque::with_capacity(BLOCK_SUB_BLOCKS);
+    let mut block_powers = Vec::new();
+
+    while let Some(sample) = source.next() {
+        let (shelf, high_pass) = &mut filters[channel];
+        let filtered = high_pass.process(shelf.process(sample.to_f32() as f64));
+        sub_block_power += channel_weight(channel) * filtered * filtered;
+
+        channel += 1;
+        if channel < channels {
+            continue;
+        }
+        channel = 0;
+        sub_block_frame += 1;
+        if sub_block_frame < sub_block_frames {
+            continue;
+        }
+
+        if sub_blocks.len() == BLOCK_SUB_BLOCKS {
+            sub_blocks.pop_front();
+        }
+        sub_blocks.push_back(sub_block_power);
+        sub_block_power = 0.0;
+        sub_block_frame = 0;
+
+        if sub_blocks.len() == BLOCK_SUB_BLOCKS {
+            let block_power =
+                sub_blocks.iter().sum::<f64>() / (BLOCK_SUB_BLOCKS * sub_block_frames) as f64;
+            block_powers.push(block_power);
+        }
+    }
+
+    let absolute_gated: Vec<f64> = block_powers
+        .into_iter()
+        .filter(|power| *power > 0.0 && power_to_loudness(*power) > ABSOLUTE_GATE_LUFS)
+        .collect();
+    if absolute_gated.is_empty() {
+        return None;
+    }
+
+    let relative_gate =
+        power_to_loudness(absolute_gated.iter().sum::<f64>() / absolute_gated.len() as f64)
+            + RELATIVE_GATE_LU;
+    let relative_gated: Vec<f64> = absolute_gated
+        .into_iter()
+        .filter(|power| power_to_loudness(*power) > relative_gate)
+        .collect();
+    if relative_gated.is_empty() {
+        return None;
+    }
+
+    Some(power_to_loudness(relative_gated.iter().sum::<f64>() / relative_gated.len() as f64) as f32)
+}
+
+/// Returns the linear gain which brings a sound with the measured loudness to the target loudness
+pub fn gain_for_target_loudness(loudness: f32, target_loudness: f32) -> f32 {
+    10f32
+        .powf((target_loudness - loudness) / 20.0)
+        .min(MAX_GAIN)
+}
+
+/// Returns the integrated loudness of the sound file at path
+///
+/// The measurement is cached in the temp dir keyed by the path and modification time of the file
+pub fn cached_integrated_loudness(path: &Path) -> Result<Option<f32>> {
+    let modified = std::fs::metadata(path)?.modified()?;
+    let cache_path = download::get_file_path_from_hash(&(path, modified)).with_extension("lufs");
+
+    if let Ok(cached) = std::fs::read_to_string(&cache_path) {
+        match serde_json::from_str(&cached) {
+            Ok(loudness) => return Ok(loudness),
+            Err(err) => warn!(
+                "invalid loudness cache file {}: {}",
+                cache_path.display(),
+                err
+            ),
+        }
+    }
+
+    info!("measuring loudness of {}", path.display());
+    let reader = std::io::BufReader::with_capacity(1000 * 50, std::fs::File::open(path)?);
+    let loudness = integrated_loudness(Decoder::new(reader)?);
+    std::fs::write(&cache_path, serde_json::to_string(&loudness)?)?;
+    Ok(loudness)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    struct SineSource {
+        amplitude: f32,
+        frequency: f32,
+        sample_rate: u32,
+        channels: u16,
+        remaining: usize,
+        index: usize,
+    }
+
+    impl SineSource {
+        fn new(amplitude: f32, channels: u16, seconds: usize) -> Self {
+            Self {
+                amplitude,
+                frequency: 1000.0,
+                sample_rate: 48000,
+                channels,
+                remaining: 48000 * seconds * channels as usize,
+                index: 0,
+            }
+        }
+    }
+
+    impl Iterator for SineSource {
+        type Item = f32;
+
+        fn next(&mut self) -> Option<f32> {
+            if self.remaining == 0 {
+                return None;
+            }
+            self.remaining -= 1;
+            let frame = self.index / self.channels as usize;
+            self.index += 1;
+            let phase = 2.0 * std::f32::consts::PI * self.frequency * frame as f32
+                / self.sample_rate as f32;
+            Some(self.amplitude * phase.sin())
+        }
+    }
+
+    impl Source for SineSource {
+        fn current_frame_len(&self) -> Option<usize> {
+            None
+        }
+        fn channels(&self) -> u16 {
+            self.channels
+        }
+        fn sample_rate(&self) -> u32 {
+            self.sample_rate
+        }
+        fn total_duration(&self) -> Option<Duration> {
+            None
+        }
+    }
+
+    #[test]
+    fn full_scale_sine() {
+        // a 0 dBFS 1kHz sine on one channel measures -3.01 LUFS
+        let loudness = integrated_loudness(SineSource::new(1.0, 1, 5)).unwrap();
+        assert!((loudness + 3.01).abs() < 0.1, "loudness {}", loudness);
+
+        // and on two channels +3dB more
+        let loudness = integrated_loudness(SineSource::new(1.0, 2, 5)).unwrap();
+        assert!(loudness.abs() < 0.1, "loudness {}", loudness);
+    }
+
+    #[test]
+    fn attenuated_sine() {
+        // -20 dBFS
+        let loudness = integrated_loudness(SineSource::new(0.1, 1, 5)).unwrap();
+        assert!((loudness + 23.01).abs() < 0.1, "loudness {}", loudness);
+    }
+
+    #[test]
+    fn silence_and_short_sources() {
+        assert_eq!(integrated_loudness(SineSource::new(0.0, 2, 2)), None);
+        assert_eq!(integrated_loudness(SineSource::new(1.0, 2, 0)), None);
+    }
+
+    #[test]
+    fn target_gain() {
+        assert!((gain_for_target_loudness(-23.0, -23.0) - 1.0).abs() < 0.001);
+        assert!((gain_for_target_loudness(-3.0, -23.0) - 0.1).abs() < 0.001);
+        assert!((gain_for_target_loudness(-60.0, -23.0) - MAX_GAIN).abs() < 0.001);
+    }
+}
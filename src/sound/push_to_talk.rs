@@ -0,0 +1,52 @@
+//! Push-to-talk gating of the microphone passthrough
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+/// Duration of the gain ramp when opening or closing the gate to avoid clicks
+const RAMP_SECONDS: f32 = 0.01;
+
+/// Mutes samples while the shared `open` flag is not set
+pub struct InputGate {
+    open: Arc<AtomicBool>,
+    step: f32,
+    gain: f32,
+}
+
+impl InputGate {
+    pub fn new(open: Arc<AtomicBool>, sample_rate: u32) -> Self {
+        let gain = if open.load(Ordering::Relaxed) {
+            1.0
+        } else {
+            0.0
+        };
+        Self {
+            open,
+            step: 1.0 / (RAMP_SECONDS * sample_rate.max(1) as f32),
+            gain,
+        }
+    }
+
+    /// Applies the gate to interleaved samples
+    pub fn process(&mut self, samples: &mut [f32], channels: usize) {
+        let target = if self.open.load(Ordering::Relaxed) {
+            1.0
+        } else {
+            0.0
+        };
+        if (self.gain - target).abs() < f32::EPSILON {
+            if target == 0.0 {
+                samples.iter_mut().for_each(|sample| *sample = 0.0);
+            }
+            return;
+        }
+        for frame in samples.chunks_mut(channels.max(1)) {
+            self.gain = if target > self.gain {
+                (self.gain + self.step).min(target)
+            } else {
+                (self.gain - self.step).max(target)
+            };
+            frame.iter_mut().for_each(|sample| *sample *= self.gain);
+        }
+    }
+}
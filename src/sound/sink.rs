@@ -23,11 +23,386 @@ struct ConverterWrapper(pub miniaudio::DataConverter);
 unsafe impl Sync for ConverterWrapper {}
 unsafe impl Send for ConverterWrapper {}
 
-type SourcesType<T, S> = std::sync::Arc<
-    parking_lot::Mutex<
-        HashMap<T, Vec<(S, VecDeque<i16>, Option<ConverterWrapper>, f32, f32, f32)>>,
-    >,
->;
+/// Peak level of the mix bus limiter in dBFS
+const LIMITER_CEILING: f32 = -0.3;
+/// Release time of the mix bus limiter in seconds
+const LIMITER_RELEASE: f32 = 0.1;
+
+/// Brickwall limiter which reduces the gain instantly and releases it smoothly
+struct Limiter {
+    ceiling: f32,
+    release_coeff: f32,
+    gain: f32,
+}
+
+impl Limiter {
+    fn new(sample_rate: u32) -> Self {
+        Self {
+            ceiling: 10f32.powf(LIMITER_CEILING / 20.0),
+            release_coeff: super::source::smoothing_coeff(LIMITER_RELEASE, sample_rate),
+            gain: 1.0,
+        }
+    }
+
+    /// Limits interleaved samples so no sample exceeds the ceiling
+    fn process(&mut self, samples: &mut [f32], channels: usize) {
+        for frame in samples.chunks_mut(channels.max(1)) {
+            let peak = frame
+                .iter()
+                .fold(0f32, |peak, sample| peak.max(sample.abs()));
+            let required = if peak > self.ceiling {
+                self.ceiling / peak
+            } else {
+                1.0
+            };
+            if required < self.gain {
+                self.gain = required;
+            } else {
+                self.gain += (required - self.gain) * self.release_coeff;
+            }
+            frame.iter_mut().for_each(|sample| *sample *= self.gain);
+        }
+    }
+}
+
+/// Options for playing a source on a `Sink`
+#[derive(Debug, Clone, Copy)]
+pub struct PlayOptions {
+    /// Start timestamp in seconds, also the loop start
+    pub start: Option<f32>,
+    /// End timestamp in seconds, also the loop end
+    pub end: Option<f32>,
+    pub volume: f32,
+    /// Fade in duration in seconds
+    pub fade_in: f32,
+    /// Fade out duration in seconds
+    pub fade_out: f32,
+    /// How often the source is played, `None` repeats it until it is removed
+    pub plays: Option<u32>,
+}
+
+impl Default for PlayOptions {
+    fn default() -> Self {
+        Self {
+            start: None,
+            end: None,
+            volume: 1.0,
+            fade_in: 0.0,
+            fade_out: 0.0,
+            plays: Some(1),
+        }
+    }
+}
+
+/// Creates the source again to play it another time
+pub type ReopenSource<S> = Box<dyn FnMut() -> Result<S> + Send>;
+
+struct SinkSource<S> {
+    source: S,
+    /// Next plays of a looping source, reopened and moved to the loop start outside of the
+    /// audio callback so restarting only swaps the source
+    next_plays: Option<crossbeam_channel::Receiver<Result<S>>>,
+    /// Waits for the next play instead of ending the source if it is not ready yet
+    wait_for_next_play: bool,
+    repeatable: bool,
+    buffer: VecDeque<f32>,
+    resampler: Option<ConverterWrapper>,
+    end: f32,
+    current_duration: f32,
+    volume: f32,
+    fade_in_samples: usize,
+    fade_out_samples: usize,
+    played_samples: usize,
+    /// Remaining samples of a running fade out
+    fade_out_remaining: Option<usize>,
+    loop_start: f32,
+    /// Remaining plays including the current one, `None` is infinite
+    remaining_plays: Option<u32>,
+    loop_count: u32,
+    paused: bool,
+}
+
+/// Moves a source which is at its beginning to the timestamp in seconds
+///
+/// Falls back to skipping samples if the source can not seek
+fn skip_source_to<S>(source: &mut S, timestamp: f32)
+where
+    S: Source,
+    S::Item: Sample,
+{
+    if let Err(err) = source.seek(Duration::from_secs_f32(timestamp)) {
+        trace!("skipping samples instead of seeking: {:#}", err);
+        let samples =
+            ((timestamp * source.sample_rate() as f32) * source.channels() as f32) as usize;
+        if samples > 0 {
+            source.nth(samples - 1);
+        }
+    }
+}
+
+/// Starts a thread which reopens the source for the next plays
+///
+/// The thread keeps one play ready and ends once the receiver is dropped
+fn spawn_next_plays<S>(
+    mut reopen: ReopenSource<S>,
+    loop_start: f32,
+) -> crossbeam_channel::Receiver<Result<S>>
+where
+    S: Source + Send + 'static,
+    S::Item: Sample,
+{
+    let (sender, receiver) = crossbeam_channel::bounded(0);
+    std::thread::spawn(move || loop {
+        let next_play = reopen().map(|mut source| {
+            skip_source_to(&mut source, loop_start);
+            source
+        });
+        let failed = next_play.is_err();
+        if sender.send(next_play).is_err() || failed {
+            break;
+        }
+    });
+    receiver
+}
+
+impl<S> SinkSource<S>
+where
+    S: Source + Send + 'static,
+    S::Item: Sample,
+{
+    fn new(source: S, options: PlayOptions, reopen: Option<ReopenSource<S>>) -> Result<Self> {
+        let start_float = {
+            let start = options.start.unwrap_or_default();
+            if start < 0.0 {
+                return Err(anyhow!("supplied start timestamp is negative {}", start));
+            }
+            start
+        };
+        let end_float = {
+            if let Some(end_duration) = options.end {
+                if end_duration < 0.0 {
+                    return Err(anyhow!(
+                        "supplied end timestamp is negative {}",
+                        end_duration
+                    ));
+                }
+                end_duration
+            } else {
+                f32::INFINITY
+            }
+        };
+        if !(options.volume.is_finite() && options.volume >= 0.0) {
+            return Err(anyhow!("supplied volume is invalid {}", options.volume));
+        }
+        if options.fade_in < 0.0 || options.fade_out < 0.0 {
+            return Err(anyhow!(
+                "supplied fade duration is negative in: {} out: {}",
+                options.fade_in,
+                options.fade_out
+            ));
+        }
+        if options.plays == Some(0) {
+            return Err(anyhow!("supplied play count is zero"));
+        }
+        if options.plays != Some(1) && reopen.is_none() {
+            return Err(anyhow!("looping a source requires a way to reopen it"));
+        }
+        let samples_per_second = (source.sample_rate() * source.channels() as u32) as f32;
+        let mut source = source;
+        // moving to the start decodes or reads the file which must not happen in the audio callback
+        skip_source_to(&mut source, start_float);
+        let repeatable = reopen.is_some();
+        let next_plays = match reopen {
+            Some(reopen) if options.plays != Some(1) => Some(spawn_next_plays(reopen, start_float)),
+            _ => None,
+        };
+        Ok(SinkSource {
+            source,
+            next_plays,
+            wait_for_next_play: false,
+            repeatable,
+            buffer: VecDeque::new(),
+            resampler: None,
+            end: end_float,
+            current_duration: start_float,
+            volume: options.volume,
+            fade_in_samples: (options.fade_in * samples_per_second) as usize,
+            fade_out_samples: (options.fade_out * samples_per_second) as usize,
+            played_samples: 0,
+            fade_out_remaining: None,
+            loop_start: start_float,
+            remaining_plays: options.plays,
+            loop_count: 0,
+            paused: false,
+        })
+    }
+
+    /// Returns the next sample of the source with the volume and fades applied
+    ///
+    /// Returns `None` after a fade out has finished
+    #[inline]
+    fn next_sample(&mut self) -> Option<f32> {
+        let mut volume = self.volume;
+        if self.played_samples < self.fade_in_samples {
+            volume *= self.played_samples as f32 / self.fade_in_samples as f32;
+        }
+        if let Some(remaining) = self.fade_out_remaining.as_mut() {
+            if *remaining == 0 {
+                return None;
+            }
+            volume *= *remaining as f32 / self.fade_out_samples as f32;
+            *remaining -= 1;
+        }
+        self.played_samples += 1;
+        let sample = match self.source.next() {
+            Some(sample) => sample,
+            None => {
+                if !self.restart() {
+                    return None;
+                }
+                self.source.next()?
+            }
+        };
+        Some(sample.to_f32() * volume)
+    }
+
+    /// Seeks the source to the timestamp in seconds
+    fn seek_to(&mut self, timestamp: f32) -> Result<()> {
+        self.source.seek(Duration::from_secs_f32(timestamp))?;
+        self.buffer.clear();
+        self.current_duration = timestamp;
+        Ok(())
+    }
+
+    fn is_last_play(&self) -> bool {
+        !self.repeatable || self.remaining_plays.map_or(false, |plays| plays <= 1)
+    }
+
+    /// Plays the source again from the loop start if plays are remaining
+    ///
+    /// Returns false if the source should end
+    fn restart(&mut self) -> bool {
+        if self.fade_out_remaining.is_some() || self.is_last_play() {
+            return false;
+        }
+        let next_play = match self.next_plays.as_ref() {
+            Some(next_plays) if self.wait_for_next_play => next_plays
+                .recv()
+                .unwrap_or_else(|_| Err(anyhow!("reopening the source failed"))),
+            Some(next_plays) => next_plays
+                .try_recv()
+                .unwrap_or_else(|_| Err(anyhow!("next play is not ready"))),
+            None => Err(anyhow!("the source can not be reopened")),
+        };
+        match next_play {
+            Ok(source) => {
+                self.source = source;
+                self.buffer.clear();
+                self.current_duration = self.loop_start;
+            }
+            Err(err) => {
+                error!("failed to restart looping source: {:#}", err);
+                return false;
+            }
+        }
+        if let Some(plays) = self.remaining_plays.as_mut() {
+            *plays -= 1;
+        }
+        self.loop_count += 1;
+        if self.is_last_play() {
+            // ends the thread reopening the source
+            self.next_plays = None;
+        }
+        true
+    }
+
+    /// Starts the fade out of the last play so it ends at the end timestamp
+    fn fade_out_before_end(&mut self) {
+        if self.end.is_finite() && self.fade_out_samples > 0 && self.is_last_play() {
+            let fade_out_duration = self.fade_out_samples as f32
+                / (self.source.sample_rate() * self.source.channels() as u32) as f32;
+            if self.current_duration >= self.end - fade_out_duration {
+                self.fade_out();
+            }
+        }
+    }
+
+    /// Starts to fade out the source, returns false if the source has no fade out
+    fn fade_out(&mut self) -> bool {
+        if self.fade_out_samples == 0 {
+            return false;
+        }
+        if self.fade_out_remaining.is_none() {
+            self.fade_out_remaining = Some(self.fade_out_samples);
+        }
+        true
+    }
+}
+
+/// Number of samples limited at once while rendering
+const RENDER_CHUNK_SAMPLES: usize = 4096;
+
+/// Renders the source with the options like a `Sink` plays it
+///
+/// The samples are passed to `write` with the sample rate and channels of the source.
+/// Sources repeated until they are removed are rendered once.
+pub fn render<S>(
+    source: S,
+    mut options: PlayOptions,
+    reopen: Option<ReopenSource<S>>,
+    mut write: impl FnMut(f32) -> Result<()>,
+) -> Result<()>
+where
+    S: Source + Send + 'static,
+    S::Item: Sample,
+{
+    if options.plays.is_none() {
+        options.plays = Some(1);
+    }
+    let channels = source.channels() as usize;
+    let samples_per_second = (source.sample_rate() * source.channels() as u32) as f64;
+    let mut limiter = Limiter::new(source.sample_rate());
+    let mut sink_source = SinkSource::new(source, options, reopen)?;
+    sink_source.wait_for_next_play = true;
+
+    let chunk_len = RENDER_CHUNK_SAMPLES - RENDER_CHUNK_SAMPLES % channels.max(1);
+    let mut chunk: Vec<f32> = Vec::with_capacity(chunk_len);
+    // the position is derived from the samples of the current play, adding the duration of
+    // each sample to a float stops advancing for long sources
+    let mut play_start = sink_source.current_duration as f64;
+    let mut play_samples: u64 = 0;
+    let mut loop_count = sink_source.loop_count;
+    loop {
+        let sample = if sink_source.current_duration >= sink_source.end && !sink_source.restart() {
+            None
+        } else {
+            sink_source.fade_out_before_end();
+            sink_source.next_sample()
+        };
+        if let Some(sample) = sample {
+            if sink_source.loop_count != loop_count {
+                loop_count = sink_source.loop_count;
+                play_start = sink_source.loop_start as f64;
+                play_samples = 0;
+            }
+            play_samples += 1;
+            sink_source.current_duration =
+                (play_start + play_samples as f64 / samples_per_second) as f32;
+            chunk.push(sample);
+        }
+        if chunk.len() == chunk_len || (sample.is_none() && !chunk.is_empty()) {
+            limiter.process(&mut chunk, channels);
+            for sample in chunk.drain(..) {
+                write(sample)?;
+            }
+        }
+        if sample.is_none() {
+            return Ok(());
+        }
+    }
+}
+
+type SourcesType<T, S> = std::sync::Arc<parking_lot::Mutex<HashMap<T, Vec<SinkSource<S>>>>>;
 
 pub struct Sink<T, S>
 where
@@ -53,16 +428,22 @@ where
     T: std::fmt::Debug,
 {
     /// Builds a new `Sink`
+    ///
+    /// The sources are mixed in floating point, attenuated by the headroom in dB and limited
     #[inline]
     pub fn new(
         context: &miniaudio::Context,
         device_id: Option<miniaudio::DeviceId>,
+        headroom: f32,
     ) -> Result<Self> {
         let mut device_config = miniaudio::DeviceConfig::new(DeviceType::Playback);
         device_config.playback_mut().set_device_id(device_id);
         device_config
             .playback_mut()
-            .set_format(miniaudio::Format::S16);
+            .set_format(miniaudio::Format::F32);
+        let headroom_gain = 10f32.powf(-headroom.abs() / 20.0);
+        let mut limiter: Option<Limiter> = None;
+        let mut mix: Vec<f32> = Vec::new();
 
         let hash_map = SourcesType::<T, S>::default();
         let hash_map_clone = hash_map.clone();
@@ -76,108 +457,111 @@ where
                 panic!("unknown format for device");
             }
 
+            mix.clear();
+            mix.resize(output.sample_count(), 0.0);
+
             for (key, sources) in unlocked.iter_mut() {
-                for (source, buffer, resampler, start, end, current_duration) in sources {
-                    if *start > 0.0 {
-                        source.nth(
-                            ((*start * source.sample_rate() as f32) * source.channels() as f32)
-                                as usize,
-                        );
-                        *current_duration = *start;
-                        *start = 0.0;
+                for (index, sink_source) in sources.iter_mut().enumerate() {
+                    if sink_source.paused {
+                        continue;
                     }
-                    if current_duration >= end {
-                        remove_keys.push(key.clone());
+                    if sink_source.current_duration >= sink_source.end && !sink_source.restart() {
+                        remove_keys.push((key.clone(), index));
                         continue;
                     }
+                    sink_source.fade_out_before_end();
 
-                    *current_duration += ((output.sample_count() / output.channels() as usize)
-                        as f32)
-                        / device.sample_rate() as f32;
+                    sink_source.current_duration +=
+                        ((output.sample_count() / output.channels() as usize) as f32)
+                            / device.sample_rate() as f32;
 
-                    if source.sample_rate() != device.sample_rate()
-                        || source.channels() != output.channels() as u16
+                    let source_sample_rate = sink_source.source.sample_rate();
+                    let source_channels = sink_source.source.channels();
+                    if source_sample_rate != device.sample_rate()
+                        || source_channels != output.channels() as u16
                     {
-                        if resampler.is_none() {
+                        if sink_source.resampler.is_none() {
                             let config = miniaudio::DataConverterConfig::new(
-                                miniaudio::Format::S16,
-                                output.format(),
-                                source.channels() as u32,
+                                miniaudio::Format::F32,
+                                miniaudio::Format::F32,
+                                source_channels as u32,
                                 output.channels(),
-                                source.sample_rate(),
+                                source_sample_rate,
                                 device.sample_rate(),
                             );
-                            *resampler = Some(ConverterWrapper(
+                            sink_source.resampler = Some(ConverterWrapper(
                                 miniaudio::DataConverter::new(&config).unwrap(),
                             ));
                         }
-                        let mut old_samples: Vec<i16> = Vec::with_capacity(output.sample_count());
+                        let mut old_samples: Vec<f32> = Vec::with_capacity(output.sample_count());
                         let mut filled_count = 0;
                         for _ in 0..output.sample_count() {
-                            if let Some(item) = buffer.pop_front() {
+                            if let Some(item) = sink_source.buffer.pop_front() {
                                 old_samples.push(item);
                                 continue;
                             }
-                            let next = source.next();
-                            if let Some(next) = next {
-                                old_samples.push(next.to_i16());
+                            if let Some(next) = sink_source.next_sample() {
+                                old_samples.push(next);
                             } else {
                                 filled_count = output.sample_count() - old_samples.len();
-                                old_samples.resize(output.sample_count() as usize, 0);
+                                old_samples.resize(output.sample_count() as usize, 0.0);
                                 break;
                             }
                         }
-                        let mut new_samples_mut: Vec<i16> = vec![0; output.sample_count()];
-                        let (_output_frame_count, input_frame_count) = resampler
+                        let mut new_samples_mut: Vec<f32> = vec![0.0; output.sample_count()];
+                        let (_output_frame_count, input_frame_count) = sink_source
+                            .resampler
                             .as_mut()
                             .unwrap()
                             .0
                             .process_pcm_frames(
                                 &mut FramesMut::wrap(
                                     &mut new_samples_mut,
-                                    output.format(),
+                                    miniaudio::Format::F32,
                                     output.channels() as u32,
                                 ),
                                 &Frames::wrap(
                                     &old_samples,
-                                    miniaudio::Format::S16,
-                                    source.channels() as u32,
+                                    miniaudio::Format::F32,
+                                    source_channels as u32,
                                 ),
                             )
                             .expect("resampling failed");
-                        let mut iterator = new_samples_mut.iter();
-                        for item in output.as_samples_mut::<i16>() {
-                            if let Some(value) = iterator.next() {
-                                *item = item.saturating_add(*value);
-                            } else {
-                                break;
-                            }
+                        for (item, value) in mix.iter_mut().zip(new_samples_mut.iter()) {
+                            *item += *value;
                         }
                         for item in old_samples
                             .iter()
-                            .skip((input_frame_count * source.channels() as u64) as usize)
+                            .skip((input_frame_count * source_channels as u64) as usize)
                             .skip(filled_count)
                         {
-                            buffer.push_back(*item);
+                            sink_source.buffer.push_back(*item);
                         }
-                        if filled_count > 0 && buffer.is_empty() {
-                            remove_keys.push(key.clone());
+                        if filled_count > 0 && sink_source.buffer.is_empty() {
+                            remove_keys.push((key.clone(), index));
                         }
                     } else {
-                        for item in output.as_samples_mut::<i16>() {
-                            if let Some(value) = source.next() {
-                                *item = item.saturating_add(value.to_i16());
+                        for item in mix.iter_mut() {
+                            if let Some(value) = sink_source.next_sample() {
+                                *item += value;
                             } else {
-                                remove_keys.push(key.clone());
+                                remove_keys.push((key.clone(), index));
                                 break;
                             }
                         }
                     }
                 }
             }
-            for key in &remove_keys {
+
+            let limiter = limiter.get_or_insert_with(|| Limiter::new(device.sample_rate()));
+            mix.iter_mut().for_each(|sample| *sample *= headroom_gain);
+            limiter.process(&mut mix, output.channels() as usize);
+            output.as_samples_mut::<f32>().copy_from_slice(&mix);
+
+            // indices of the same key are ascending so removing in reverse keeps them valid
+            for (key, index) in remove_keys.iter().rev() {
                 let entry: &mut Vec<_> = unlocked.get_mut(key).unwrap();
-                entry.remove(0);
+                entry.remove(*index);
                 if entry.is_empty() {
                     unlocked.remove(key);
                 }
@@ -188,7 +572,7 @@ where
             stopped_clone.store(true, std::sync::atomic::Ordering::Relaxed);
         });
         let device = miniaudio::Device::new(Some(context.clone()), &device_config)
-            .expect("failed to create miniaudio device");
+            .map_err(|err| anyhow!("Could not create device {}", err))?;
         Ok(Sink {
             device,
             stopped,
@@ -196,50 +580,46 @@ where
         })
     }
 
-    pub fn play(&mut self, key: T, source: S, start: Option<f32>, end: Option<f32>) -> Result<()> {
+    /// Plays the source under the key
+    ///
+    /// `reopen` is needed to play the source more than once
+    pub fn play(
+        &mut self,
+        key: T,
+        source: S,
+        options: PlayOptions,
+        reopen: Option<ReopenSource<S>>,
+    ) -> Result<()> {
+        let sink_source = SinkSource::new(source, options, reopen)?;
         let mut unlocked = self.sources.lock();
-        let start_float = {
-            let start = start.unwrap_or_default();
-            if start < 0.0 {
-                return Err(anyhow!("supplied start timestamp is negative {}", start));
-            }
-            start
-        };
-        let end_float = {
-            if let Some(end_duration) = end {
-                if end_duration < 0.0 {
-                    return Err(anyhow!(
-                        "supplied end timestamp is negative {}",
-                        end_duration
-                    ));
-                }
-                end_duration
-            } else {
-                f32::INFINITY
-            }
-        };
         match unlocked.entry(key) {
             std::collections::hash_map::Entry::Occupied(mut entry) => {
                 let entry = entry.get_mut();
-                entry.push((source, VecDeque::new(), None, start_float, end_float, 0.0));
+                entry.push(sink_source);
             }
             std::collections::hash_map::Entry::Vacant(entry) => {
-                entry.insert(vec![(
-                    source,
-                    VecDeque::new(),
-                    None,
-                    start_float,
-                    end_float,
-                    0.0,
-                )]);
+                entry.insert(vec![sink_source]);
             }
         }
         Ok(())
     }
 
+    /// Stops all sources of the key
+    ///
+    /// Sources with a fade out keep playing until they are faded out
     pub fn remove(&mut self, key: &T) {
         let mut unlocked = self.sources.lock();
-        unlocked.remove(key);
+        if let Some(sources) = unlocked.get_mut(key) {
+            // paused sources are skipped by the data callback and would never finish their fade
+            sources.retain(|sink_source| sink_source.fade_out_samples > 0 && !sink_source.paused);
+            if sources.is_empty() {
+                unlocked.remove(key);
+                return;
+            }
+            for sink_source in sources.iter_mut() {
+                sink_source.fade_out();
+            }
+        }
     }
 
     pub fn is_playing(&mut self, key: &T) -> bool {
@@ -247,6 +627,50 @@ where
         unlocked.contains_key(&key)
     }
 
+    /// Returns the play position in the current loop and the number of completed loops
+    /// of the latest source of the key
+    pub fn play_status(&self, key: &T) -> Option<(Duration, u32)> {
+        let unlocked = self.sources.lock();
+        unlocked
+            .get(key)
+            .and_then(|sources| sources.last())
+            .map(|sink_source| {
+                let position = (sink_source.current_duration - sink_source.loop_start).max(0.0);
+                (Duration::from_secs_f32(position), sink_source.loop_count)
+            })
+    }
+
+    /// Seeks all sources of the key to the position relative to their start timestamp
+    pub fn seek(&mut self, key: &T, position: Duration) -> Result<()> {
+        let mut unlocked = self.sources.lock();
+        let sources = unlocked
+            .get_mut(key)
+            .ok_or_else(|| anyhow!("no source playing for {:?}", key))?;
+        for sink_source in sources.iter_mut() {
+            let timestamp = (sink_source.loop_start + position.as_secs_f32()).min(sink_source.end);
+            sink_source.seek_to(timestamp)?;
+        }
+        Ok(())
+    }
+
+    /// Pauses or resumes all sources of the key without dropping them
+    pub fn set_paused(&mut self, key: &T, paused: bool) {
+        let mut unlocked = self.sources.lock();
+        if let Some(sources) = unlocked.get_mut(key) {
+            for sink_source in sources.iter_mut() {
+                sink_source.paused = paused;
+            }
+        }
+    }
+
+    /// Pauses or resumes all sources
+    pub fn set_all_paused(&mut self, paused: bool) {
+        let mut unlocked = self.sources.lock();
+        for sink_source in unlocked.values_mut().flatten() {
+            sink_source.paused = paused;
+        }
+    }
+
     /// Gets the volume of the sound.
     ///
     /// The value `1.0` is the "normal" volume (unfiltered input). Any value other than 1.0 will
@@ -2,8 +2,11 @@
 
 //! Sources of sound and various filters.
 
+use anyhow::{anyhow, Result};
+use std::sync::Arc;
 use std::time::Duration;
 
+use super::effects::PitchShifter;
 use super::sample::Sample;
 
 ///
@@ -29,6 +32,13 @@ where
     ///
     /// `None` indicates at the same time "infinite" or "unknown".
     fn total_duration(&self) -> Option<Duration>;
+
+    /// Seeks to the position from the start of the source.
+    ///
+    /// Returns an error if the source does not support seeking.
+    fn seek(&mut self, _position: Duration) -> Result<()> {
+        Err(anyhow!("seeking is not supported by this source"))
+    }
 }
 
 impl<S> Source for Box<dyn Source<Item = S>>
@@ -54,6 +64,11 @@ where
     fn total_duration(&self) -> Option<Duration> {
         (**self).total_duration()
     }
+
+    #[inline]
+    fn seek(&mut self, position: Duration) -> Result<()> {
+        (**self).seek(position)
+    }
 }
 
 impl<S> Source for Box<dyn Source<Item = S> + Send>
@@ -79,6 +94,11 @@ where
     fn total_duration(&self) -> Option<Duration> {
         (**self).total_duration()
     }
+
+    #[inline]
+    fn seek(&mut self, position: Duration) -> Result<()> {
+        (**self).seek(position)
+    }
 }
 
 impl<S> Source for Box<dyn Source<Item = S> + Send + Sync>
@@ -104,4 +124,609 @@ where
     fn total_duration(&self) -> Option<Duration> {
         (**self).total_duration()
     }
+
+    #[inline]
+    fn seek(&mut self, position: Duration) -> Result<()> {
+        (**self).seek(position)
+    }
+}
+
+/// Returns the coefficient of a one pole smoothing filter with the time constant in seconds
+pub fn smoothing_coeff(time: f32, sample_rate: u32) -> f32 {
+    if time <= 0.0 || sample_rate == 0 {
+        1.0
+    } else {
+        1.0 - (-1.0 / (time * sample_rate as f32)).exp()
+    }
+}
+
+fn db_to_linear(db: f32) -> f32 {
+    10f32.powf(db / 20.0)
+}
+
+/// Filters which process f32 sources and can be chained
+pub trait FilterExt: Source<Item = f32> + Sized {
+    /// Mutes the source while its level is below the threshold in dBFS
+    fn noise_gate(self, threshold: f32) -> NoiseGate<Self> {
+        NoiseGate::new(self, threshold)
+    }
+
+    /// Removes frequencies below the cutoff frequency in Hz
+    fn high_pass(self, frequency: f32) -> HighPass<Self> {
+        HighPass::new(self, frequency)
+    }
+
+    /// Reduces the level above the threshold in dBFS by ratio and limits the peaks
+    fn compressor(self, threshold: f32, ratio: f32) -> Compressor<Self> {
+        Compressor::new(self, threshold, ratio)
+    }
+}
+
+impl<S> FilterExt for S where S: Source<Item = f32> {}
+
+/// Source which plays the samples pushed into it, used to feed captured frames into filters
+///
+/// Returns `None` while no samples are buffered.
+pub struct BufferedSource {
+    buffer: std::collections::VecDeque<f32>,
+    channels: u16,
+    sample_rate: u32,
+}
+
+impl BufferedSource {
+    pub fn new(channels: u16, sample_rate: u32) -> Self {
+        Self {
+            buffer: std::collections::VecDeque::new(),
+            channels,
+            sample_rate,
+        }
+    }
+
+    /// Appends interleaved samples
+    pub fn push(&mut self, samples: &[f32]) {
+        self.buffer.extend(samples);
+    }
+}
+
+impl Iterator for BufferedSource {
+    type Item = f32;
+
+    #[inline]
+    fn next(&mut self) -> Option<f32> {
+        self.buffer.pop_front()
+    }
+}
+
+impl Source for BufferedSource {
+    #[inline]
+    fn current_frame_len(&self) -> Option<usize> {
+        None
+    }
+
+    #[inline]
+    fn channels(&self) -> u16 {
+        self.channels
+    }
+
+    #[inline]
+    fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+
+    #[inline]
+    fn total_duration(&self) -> Option<Duration> {
+        None
+    }
+}
+
+macro_rules! impl_filter_source {
+    ($name:ident) => {
+        impl<S> $name<S>
+        where
+            S: Source<Item = f32>,
+        {
+            /// Returns a mutable reference to the inner source
+            #[inline]
+            pub fn inner_mut(&mut self) -> &mut S {
+                &mut self.input
+            }
+
+            /// Passes the samples through unchanged while disabled
+            #[inline]
+            pub fn set_enabled(&mut self, enabled: bool) {
+                self.enabled = enabled;
+            }
+        }
+
+        impl<S> Source for $name<S>
+        where
+            S: Source<Item = f32>,
+        {
+            #[inline]
+            fn current_frame_len(&self) -> Option<usize> {
+                self.input.current_frame_len()
+            }
+
+            #[inline]
+            fn channels(&self) -> u16 {
+                self.input.channels()
+            }
+
+            #[inline]
+            fn sample_rate(&self) -> u32 {
+                self.input.sample_rate()
+            }
+
+            #[inline]
+            fn total_duration(&self) -> Option<Duration> {
+                self.input.total_duration()
+            }
+
+            #[inline]
+            fn seek(&mut self, position: Duration) -> Result<()> {
+                self.input.seek(position)
+            }
+        }
+    };
+}
+
+/// Filter that mutes the source while the level is below a threshold
+pub struct NoiseGate<S> {
+    input: S,
+    enabled: bool,
+    threshold: f32,
+    envelope: f32,
+    envelope_coeff: f32,
+    open_coeff: f32,
+    close_coeff: f32,
+    hold_samples: usize,
+    hold_remaining: usize,
+    gain: f32,
+}
+
+impl<S> NoiseGate<S>
+where
+    S: Source<Item = f32>,
+{
+    pub fn new(input: S, threshold: f32) -> Self {
+        // channels are interleaved and share the envelope
+        let rate = input.sample_rate() * input.channels() as u32;
+        Self {
+            enabled: true,
+            threshold: db_to_linear(threshold),
+            envelope: 0.0,
+            envelope_coeff: smoothing_coeff(0.05, rate),
+            open_coeff: smoothing_coeff(0.002, rate),
+            close_coeff: smoothing_coeff(0.05, rate),
+            hold_samples: (0.1 * rate as f32) as usize,
+            hold_remaining: 0,
+            gain: 0.0,
+            input,
+        }
+    }
+}
+
+impl<S> Iterator for NoiseGate<S>
+where
+    S: Source<Item = f32>,
+{
+    type Item = f32;
+
+    #[inline]
+    fn next(&mut self) -> Option<f32> {
+        let sample = self.input.next()?;
+        if !self.enabled {
+            return Some(sample);
+        }
+
+        let level = sample.abs();
+        if level > self.envelope {
+            self.envelope = level;
+        } else {
+            self.envelope += (level - self.envelope) * self.envelope_coeff;
+        }
+
+        if self.envelope > self.threshold {
+            self.hold_remaining = self.hold_samples;
+        } else {
+            self.hold_remaining = self.hold_remaining.saturating_sub(1);
+        }
+
+        if self.hold_remaining > 0 {
+            self.gain += (1.0 - self.gain) * self.open_coeff;
+        } else {
+            self.gain -= self.gain * self.close_coeff;
+        }
+        Some(sample * self.gain)
+    }
+}
+
+impl_filter_source!(NoiseGate);
+
+/// Second order butterworth high pass filter
+pub struct HighPass<S> {
+    input: S,
+    enabled: bool,
+    b0: f32,
+    b1: f32,
+    b2: f32,
+    a1: f32,
+    a2: f32,
+    // x1, x2, y1, y2 for every channel
+    state: Vec<[f32; 4]>,
+    channel: usize,
+}
+
+impl<S> HighPass<S>
+where
+    S: Source<Item = f32>,
+{
+    pub fn new(input: S, frequency: f32) -> Self {
+        let w0 = 2.0 * std::f32::consts::PI * frequency / input.sample_rate().max(1) as f32;
+        let alpha = w0.sin() / (2.0 * std::f32::consts::FRAC_1_SQRT_2);
+        let cos_w0 = w0.cos();
+        let a0 = 1.0 + alpha;
+
+        Self {
+            enabled: true,
+            b0: (1.0 + cos_w0) / 2.0 / a0,
+            b1: -(1.0 + cos_w0) / a0,
+            b2: (1.0 + cos_w0) / 2.0 / a0,
+            a1: -2.0 * cos_w0 / a0,
+            a2: (1.0 - alpha) / a0,
+            state: vec![[0.0; 4]; input.channels().max(1) as usize],
+            channel: 0,
+            input,
+        }
+    }
+}
+
+impl<S> Iterator for HighPass<S>
+where
+    S: Source<Item = f32>,
+{
+    type Item = f32;
+
+    #[inline]
+    fn next(&mut self) -> Option<f32> {
+        let x = self.input.next()?;
+        let channel = self.channel;
+        self.channel = (self.channel + 1) % self.state.len();
+        let state = &mut self.state[channel];
+
+        let y = self.b0 * x + self.b1 * state[0] + self.b2 * state[1]
+            - self.a1 * state[2]
+            - self.a2 * state[3];
+        *state = [x, state[0], y, state[2]];
+
+        if self.enabled {
+            Some(y)
+        } else {
+            Some(x)
+        }
+    }
+}
+
+impl_filter_source!(HighPass);
+
+/// Peak level of the limiter after the compressor in dBFS
+const LIMITER_CEILING: f32 = -1.0;
+
+/// Feed forward compressor followed by a hard limiter
+pub struct Compressor<S> {
+    input: S,
+    enabled: bool,
+    threshold: f32,
+    ratio: f32,
+    ceiling: f32,
+    envelope: f32,
+    attack_coeff: f32,
+    release_coeff: f32,
+}
+
+impl<S> Compressor<S>
+where
+    S: Source<Item = f32>,
+{
+    pub fn new(input: S, threshold: f32, ratio: f32) -> Self {
+        // channels are interleaved and share the envelope
+        let rate = input.sample_rate() * input.channels() as u32;
+        Self {
+            enabled: true,
+            threshold,
+            ratio: ratio.max(1.0),
+            ceiling: db_to_linear(LIMITER_CEILING),
+            envelope: 0.0,
+            attack_coeff: smoothing_coeff(0.005, rate),
+            release_coeff: smoothing_coeff(0.1, rate),
+            input,
+        }
+    }
+}
+
+impl<S> Iterator for Compressor<S>
+where
+    S: Source<Item = f32>,
+{
+    type Item = f32;
+
+    #[inline]
+    fn next(&mut self) -> Option<f32> {
+        let sample = self.input.next()?;
+        if !self.enabled {
+            return Some(sample);
+        }
+
+        let level = sample.abs();
+        let coeff = if level > self.envelope {
+            self.attack_coeff
+        } else {
+            self.release_coeff
+        };
+        self.envelope += (level - self.envelope) * coeff;
+
+        let over = 20.0 * self.envelope.max(1e-6).log10() - self.threshold;
+        let sample = if over > 0.0 {
+            sample * db_to_linear(-over * (1.0 - 1.0 / self.ratio))
+        } else {
+            sample
+        };
+        Some(sample.max(-self.ceiling).min(self.ceiling))
+    }
+}
+
+impl_filter_source!(Compressor);
+
+/// Changes the playback speed and with it the pitch like a tape
+pub struct Speed<S> {
+    input: S,
+    speed: f32,
+}
+
+impl<S> Speed<S>
+where
+    S: Source,
+    S::Item: Sample,
+{
+    pub fn new(input: S, speed: f32) -> Self {
+        Self { input, speed }
+    }
+}
+
+impl<S> Iterator for Speed<S>
+where
+    S: Source,
+    S::Item: Sample,
+{
+    type Item = S::Item;
+
+    #[inline]
+    fn next(&mut self) -> Option<S::Item> {
+        self.input.next()
+    }
+}
+
+impl<S> Source for Speed<S>
+where
+    S: Source,
+    S::Item: Sample,
+{
+    #[inline]
+    fn current_frame_len(&self) -> Option<usize> {
+        self.input.current_frame_len()
+    }
+
+    #[inline]
+    fn channels(&self) -> u16 {
+        self.input.channels()
+    }
+
+    #[inline]
+    fn sample_rate(&self) -> u32 {
+        (self.input.sample_rate() as f32 * self.speed) as u32
+    }
+
+    #[inline]
+    fn total_duration(&self) -> Option<Duration> {
+        self.input
+            .total_duration()
+            .map(|duration| duration.div_f32(self.speed))
+    }
+
+    #[inline]
+    fn seek(&mut self, position: Duration) -> Result<()> {
+        self.input.seek(position.mul_f32(self.speed))
+    }
+}
+
+/// Shifts the pitch by semitones without changing the duration
+pub struct PitchShift<S> {
+    input: S,
+    ratio: f32,
+    shifters: Vec<PitchShifter>,
+    channel: usize,
+}
+
+impl<S> PitchShift<S>
+where
+    S: Source,
+    S::Item: Sample,
+{
+    pub fn new(input: S, semitones: f32) -> Self {
+        Self {
+            ratio: 2f32.powf(semitones / 12.0),
+            shifters: Self::create_shifters(&input),
+            channel: 0,
+            input,
+        }
+    }
+
+    fn create_shifters(input: &S) -> Vec<PitchShifter> {
+        (0..input.channels().max(1))
+            .map(|_| PitchShifter::new(input.sample_rate()))
+            .collect()
+    }
+}
+
+impl<S> Iterator for PitchShift<S>
+where
+    S: Source,
+    S::Item: Sample,
+{
+    type Item = S::Item;
+
+    #[inline]
+    fn next(&mut self) -> Option<S::Item> {
+        let sample = self.input.next()?;
+        let shifted = self.shifters[self.channel].process(sample.to_f32(), self.ratio);
+        self.channel = (self.channel + 1) % self.shifters.len();
+        Some(S::Item::from(&shifted))
+    }
+}
+
+impl<S> Source for PitchShift<S>
+where
+    S: Source,
+    S::Item: Sample,
+{
+    #[inline]
+    fn current_frame_len(&self) -> Option<usize> {
+        self.input.current_frame_len()
+    }
+
+    #[inline]
+    fn channels(&self) -> u16 {
+        self.input.channels()
+    }
+
+    #[inline]
+    fn sample_rate(&self) -> u32 {
+        self.input.sample_rate()
+    }
+
+    #[inline]
+    fn total_duration(&self) -> Option<Duration> {
+        self.input.total_duration()
+    }
+
+    #[inline]
+    fn seek(&mut self, position: Duration) -> Result<()> {
+        self.input.seek(position)?;
+        self.shifters = Self::create_shifters(&self.input);
+        self.channel = 0;
+        Ok(())
+    }
+}
+
+/// Frames of a source in reverse order, the source is decoded completely on creation
+///
+/// Cloning shares the samples so all plays of a sound decode it only once
+#[derive(Clone, PartialEq)]
+pub struct ReversedSamples<I> {
+    samples: Arc<Vec<I>>,
+    channels: u16,
+    sample_rate: u32,
+}
+
+impl<I> std::fmt::Debug for ReversedSamples<I> {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.debug_struct("ReversedSamples")
+            .field("samples", &self.samples.len())
+            .field("channels", &self.channels)
+            .field("sample_rate", &self.sample_rate)
+            .finish()
+    }
+}
+
+impl<I> ReversedSamples<I>
+where
+    I: Sample,
+{
+    pub fn new<S>(input: S) -> Self
+    where
+        S: Source<Item = I>,
+    {
+        let channels = input.channels();
+        let sample_rate = input.sample_rate();
+        let samples: Vec<I> = input.collect();
+        // reverse the frames but keep the order of the channels
+        let samples = samples
+            .chunks(channels.max(1) as usize)
+            .rev()
+            .flatten()
+            .copied()
+            .collect();
+        Self {
+            samples: Arc::new(samples),
+            channels,
+            sample_rate,
+        }
+    }
+
+    /// Returns a source which plays the samples from the start
+    pub fn source(&self) -> Reverse<I> {
+        Reverse {
+            samples: self.samples.clone(),
+            position: 0,
+            channels: self.channels,
+            sample_rate: self.sample_rate,
+        }
+    }
+}
+
+/// Plays the source backwards
+pub struct Reverse<I> {
+    samples: Arc<Vec<I>>,
+    position: usize,
+    channels: u16,
+    sample_rate: u32,
+}
+
+impl<I> Iterator for Reverse<I>
+where
+    I: Sample,
+{
+    type Item = I;
+
+    #[inline]
+    fn next(&mut self) -> Option<I> {
+        let sample = self.samples.get(self.position)?;
+        self.position += 1;
+        Some(*sample)
+    }
+}
+
+impl<I> Source for Reverse<I>
+where
+    I: Sample,
+{
+    #[inline]
+    fn current_frame_len(&self) -> Option<usize> {
+        Some(self.samples.len() - self.position)
+    }
+
+    #[inline]
+    fn channels(&self) -> u16 {
+        self.channels
+    }
+
+    #[inline]
+    fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+
+    #[inline]
+    fn total_duration(&self) -> Option<Duration> {
+        let frames = self.samples.len() / self.channels.max(1) as usize;
+        Some(Duration::from_secs_f64(
+            frames as f64 / self.sample_rate.max(1) as f64,
+        ))
+    }
+
+    #[inline]
+    fn seek(&mut self, position: Duration) -> Result<()> {
+        let frame = (position.as_secs_f64() * self.sample_rate as f64) as usize;
+        self.position = (frame * self.channels as usize).min(self.samples.len());
+        Ok(())
+    }
 }
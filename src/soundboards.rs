@@ -2,7 +2,6 @@
 
 use super::hotkey;
 use super::sound;
-use super::utils;
 use anyhow::{anyhow, Context, Result};
 use clap::{crate_authors, crate_description, crate_version, App, Arg};
 use log::{error, info, trace, warn};
@@ -18,6 +17,7 @@ use std::hash::{Hash, Hasher};
 use std::path::Path;
 use std::path::PathBuf;
 use std::str::FromStr;
+use std::sync::atomic::{AtomicUsize, Ordering};
 use ulid::Ulid;
 
 mod helpers;
@@ -44,14 +44,85 @@ pub fn get_soundboards() -> std::sync::Arc<SoundboardMap> {
     GLOBAL_SOUNDBOARD_MAP.read().clone()
 }
 
+static SOUNDBOARDS_VERSION: AtomicUsize = AtomicUsize::new(0);
+
+/// Returns a number which changes whenever the soundboards were changed on disk
+pub fn get_soundboards_version() -> usize {
+    SOUNDBOARDS_VERSION.load(Ordering::Relaxed)
+}
+
 /// Reloads all soundboards from disk
 ///
+/// The ids of soundboards and sounds which still exist are kept.
 /// Expensive
 pub fn reload_soundboards_from_disk() -> Result<()> {
-    *GLOBAL_SOUNDBOARD_MAP.write() = std::sync::Arc::new(load_and_parse_soundboards()?);
+    let soundboards = load_and_parse_soundboards()?;
+    let old_soundboards = get_soundboards();
+    let mut new_soundboards = SoundboardMap::default();
+    for (_, mut soundboard) in soundboards.into_iter() {
+        // soundboards without a persisted id are matched by their path
+        let old_soundboard = old_soundboards.get(&soundboard.id).or_else(|| {
+            old_soundboards
+                .values()
+                .find(|old| soundboard.loaded_without_id && old.get_path() == soundboard.get_path())
+        });
+        if let Some(old_soundboard) = old_soundboard {
+            soundboard.keep_ids_of(old_soundboard);
+        }
+        new_soundboards.insert(soundboard.id, soundboard);
+    }
+    if *old_soundboards == new_soundboards {
+        return Ok(());
+    }
+    *GLOBAL_SOUNDBOARD_MAP.write() = std::sync::Arc::new(new_soundboards);
+    SOUNDBOARDS_VERSION.fetch_add(1, Ordering::Relaxed);
     Ok(())
 }
 
+/// Time between checks of the soundboards directory for changes
+const WATCH_INTERVAL: std::time::Duration = std::time::Duration::from_secs(1);
+
+/// Returns the modification times of the soundboard files
+fn soundboard_file_times() -> Result<Vec<(PathBuf, std::time::SystemTime)>> {
+    let mut times = Vec::new();
+    for entry in std::fs::read_dir(get_soundboards_path()?)? {
+        let path = entry?.path();
+        let extension = path.extension().unwrap_or_default().to_string_lossy();
+        if extension == "toml" || extension == "json" {
+            let modified = fs::metadata(&path)?.modified()?;
+            times.push((path, modified));
+        }
+    }
+    times.sort();
+    Ok(times)
+}
+
+/// Starts a thread which reloads the soundboards when their files change
+pub fn watch_soundboards() {
+    std::thread::spawn(|| {
+        let mut last_times = soundboard_file_times().unwrap_or_default();
+        loop {
+            std::thread::sleep(WATCH_INTERVAL);
+            let times = match soundboard_file_times() {
+                Ok(times) => times,
+                Err(err) => {
+                    trace!("failed to check soundboards for changes {:#}", err);
+                    continue;
+                }
+            };
+            if times == last_times {
+                continue;
+            }
+            // a failed reload is retried with the next change of the files
+            last_times = times;
+            match reload_soundboards_from_disk() {
+                Ok(()) => info!("reloaded changed soundboards"),
+                Err(err) => error!("failed to reload changed soundboards {:#}", err),
+            }
+        }
+    });
+}
+
 // pub fn save_soundboards_to_disk() -> Result<()> {
 //     for soundboard in get_soundboards().values() {
 //         soundboard.clone().save_to_disk();
@@ -59,11 +130,134 @@ pub fn reload_soundboards_from_disk() -> Result<()> {
 //     Ok(())
 // }
 
+/// Saves the soundboard and replaces it in the soundboards
+///
+/// The previous state of the soundboard is recorded in its edit history
 pub fn update_soundboards(mut soundboard: Soundboard) -> Result<()> {
+    let previous = get_soundboard(soundboard.id).map(|s| s.to_config());
     soundboard.save_to_disk()?;
+    if let Some(previous) = previous {
+        if previous != soundboard.to_config() {
+            record_history(&soundboard, previous);
+        }
+    }
+    insert_soundboard(soundboard);
+    Ok(())
+}
+
+fn insert_soundboard(soundboard: Soundboard) {
     let mut cloned_map = (**GLOBAL_SOUNDBOARD_MAP.read()).clone();
     cloned_map.insert(soundboard.id, soundboard);
     *GLOBAL_SOUNDBOARD_MAP.write() = std::sync::Arc::new(cloned_map);
+}
+
+/// Maximum number of edits of a soundboard which can be undone
+const HISTORY_LIMIT: usize = 50;
+
+/// Edit history of a soundboard
+///
+/// Persisted as json next to the soundboard file with the extension `.history`
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+struct History {
+    undo: Vec<SoundboardConfig>,
+    redo: Vec<SoundboardConfig>,
+}
+
+impl History {
+    fn load(soundboard_path: &Path) -> Self {
+        let path = soundboard_path.with_extension("history");
+        if !path.exists() {
+            return Self::default();
+        }
+        match fs::read_to_string(&path)
+            .map_err(anyhow::Error::from)
+            .and_then(|s| Ok(serde_json::from_str(&s)?))
+        {
+            Ok(history) => history,
+            Err(err) => {
+                warn!("ignoring invalid history {}: {:#}", path.display(), err);
+                Self::default()
+            }
+        }
+    }
+
+    fn save(&self, soundboard_path: &Path) -> Result<()> {
+        let path = soundboard_path.with_extension("history");
+        let json_string =
+            serde_json::to_string(self).context("failed to serialize soundboard history")?;
+        fs::write(&path, json_string).with_context(|| format!("Failed to write {}", path.display()))
+    }
+
+    /// Records the state before an edit, edits which were undone can not be redone anymore
+    fn record(&mut self, previous: SoundboardConfig) {
+        push_bounded(&mut self.undo, previous);
+        self.redo.clear();
+    }
+}
+
+fn push_bounded(stack: &mut Vec<SoundboardConfig>, config: SoundboardConfig) {
+    stack.push(config);
+    if stack.len() > HISTORY_LIMIT {
+        stack.remove(0);
+    }
+}
+
+static HISTORIES: Lazy<parking_lot::Mutex<HashMap<SoundboardId, History>>> =
+    Lazy::new(Default::default);
+
+fn record_history(soundboard: &Soundboard, previous: SoundboardConfig) {
+    let mut histories = HISTORIES.lock();
+    let history = histories
+        .entry(soundboard.id)
+        .or_insert_with(|| History::load(soundboard.get_path()));
+    history.record(previous);
+    if let Err(err) = history.save(soundboard.get_path()) {
+        warn!("failed to save soundboard history {:#}", err);
+    }
+}
+
+/// Restores the state of the soundboard before its last edit
+pub fn undo_soundboard(id: SoundboardId) -> Result<()> {
+    restore_from_history(id, true)
+}
+
+/// Restores the state of the soundboard before its last undo
+pub fn redo_soundboard(id: SoundboardId) -> Result<()> {
+    restore_from_history(id, false)
+}
+
+fn restore_from_history(id: SoundboardId, undo: bool) -> Result<()> {
+    let current = get_soundboards()
+        .get(&id)
+        .cloned()
+        .ok_or_else(|| anyhow!("no soundboard with specified id"))?;
+    let mut histories = HISTORIES.lock();
+    let history = histories
+        .entry(id)
+        .or_insert_with(|| History::load(current.get_path()));
+    let (from, to) = if undo {
+        (&mut history.undo, &mut history.redo)
+    } else {
+        (&mut history.redo, &mut history.undo)
+    };
+    let config = from
+        .last()
+        .cloned()
+        .ok_or_else(|| anyhow!("nothing to {}", if undo { "undo" } else { "redo" }))?;
+
+    let mut restored = Soundboard::from_config(current.get_path(), config)?;
+    // changes on disk since the last load are merged like with any other save
+    restored.last_config = current.last_config.clone();
+    restored.save_to_disk()?;
+
+    from.pop();
+    push_bounded(to, current.to_config());
+    if let Err(err) = history.save(restored.get_path()) {
+        warn!("failed to save soundboard history {:#}", err);
+    }
+    drop(histories);
+
+    insert_soundboard(restored);
     Ok(())
 }
 
@@ -92,6 +286,28 @@ pub fn get_sound(soundboard_id: Ulid, sound_id: Ulid) -> Option<Sound> {
     None
 }
 
+/// Returns the devices a sound is played on if the client does not choose them
+///
+/// The routing of the sound overrides the routing of its soundboard, defaults to `SoundDevices::Both`
+pub fn find_sound_devices(sound_id: Ulid) -> sound::SoundDevices {
+    for soundboard in GLOBAL_SOUNDBOARD_MAP.read().values() {
+        if let Some(sound) = soundboard.get_sounds().get(&sound_id) {
+            return soundboard.get_sound_devices(sound);
+        }
+    }
+    sound::SoundDevices::Both
+}
+
+/// Returns the path of the image icon of the sound if it has one
+pub fn find_sound_icon_path(sound_id: Ulid) -> Option<PathBuf> {
+    for soundboard in GLOBAL_SOUNDBOARD_MAP.read().values() {
+        if let Some(sound) = soundboard.get_sounds().get(&sound_id) {
+            return soundboard.get_sound_icon_path(sound).ok().flatten();
+        }
+    }
+    None
+}
+
 /// Iterates through all soundboards and checks for the specified sound_id
 pub fn find_sound(sound_id: Ulid) -> Option<Sound> {
     for soundboard in GLOBAL_SOUNDBOARD_MAP.read().values() {
@@ -111,12 +327,16 @@ pub struct Soundboard {
     name: String,
     hotkey: Option<hotkey::Hotkey>,
     position: Option<usize>,
+    devices: Option<sound::SoundDevices>,
     sounds: SoundMap,
     sound_positions: SoundPositions,
 
     id: SoundboardId,
     path: PathBuf,
-    last_hash: Option<u64>,
+    /// config on disk at the last load or save with the ids of the sounds, base of the merge
+    last_config: Option<SoundboardConfig>,
+    /// the id was generated because the config on disk has none
+    loaded_without_id: bool,
 }
 
 impl Soundboard {
@@ -133,17 +353,19 @@ impl Soundboard {
             name: name.to_owned(),
             hotkey: None,
             position: None,
+            devices: None,
             sounds: SoundMap::default(),
             sound_positions: Vec::new(),
             id: Ulid::new(),
             path,
-            last_hash: None,
+            last_config: None,
+            loaded_without_id: false,
         })
     }
 
     fn from_config(soundboard_path: &Path, config: SoundboardConfig) -> Result<Self> {
         let mut sound_map = SoundMap::default();
-        let hash = utils::calculate_hash(&config);
+        let mut last_config = config.clone();
         let mut sound_positions = Vec::new();
         if let Some(sound_configs) = config.sounds {
             for sound_config in sound_configs {
@@ -159,40 +381,66 @@ impl Soundboard {
                 None
             }
         };
+        let loaded_without_id = config.id.is_none();
+        let id = config.id.unwrap_or_else(Ulid::new);
+        set_config_ids(&mut last_config, id, &sound_positions);
         Ok(Self {
-            last_hash: Some(hash),
+            last_config: Some(last_config),
             name: config.name,
             position: config.position,
+            devices: config.devices,
             hotkey,
             sounds: sound_map,
             sound_positions,
             path: PathBuf::from(soundboard_path),
-            id: Ulid::new(),
+            id,
+            loaded_without_id,
         })
     }
 
-    /// Save soundboard to disk
-    ///
-    /// fails if soundboard on disk was modified from our last load from disk
-    pub fn save_to_disk(&mut self) -> Result<()> {
+    fn to_config(&self) -> SoundboardConfig {
         let mut config = SoundboardConfig::new(self.name.as_str());
+        config.id = Some(self.id);
 
         if let Some(hotkey) = self.get_hotkey() {
             config.hotkey = Some(hotkey.to_string());
         }
 
         config.position = *self.get_position();
+        config.devices = self.devices.clone();
         config.sounds = Some(
             self.iter()
                 .map(|s| SoundConfig::from(s))
                 .collect::<Vec<SoundConfig>>(),
         );
+        config
+    }
 
-        save_soundboard_config(self.path.as_path(), &config, self.last_hash)?;
+    /// Save soundboard to disk
+    ///
+    /// Changes of the soundboard file on disk since the last load are merged with the changes
+    /// of the soundboard, fails with a `MergeConflict` if both changed the same sound or setting
+    pub fn save_to_disk(&mut self) -> Result<()> {
+        let mut config = self.to_config();
+
+        if let Some(base) = self.last_config.as_ref() {
+            if self.path.exists() {
+                let mut theirs = load_soundboard_config(&self.path).with_context(|| {
+                    format!("Failed to load soundboard {}", self.path.display())
+                })?;
+                assign_ids_from_base(&mut theirs, base);
+                if theirs != *base {
+                    info!("merging changes of {} on disk", self.path.display());
+                    config = merge_soundboard_configs(base, &config, &theirs)?;
+                }
+            }
+        }
 
-        self.path.set_extension("json");
+        let json_path =
+            save_soundboard_config(self.path.as_path(), &config, self.last_config.is_some())?;
 
-        self.last_hash = Some(utils::calculate_hash(&config));
+        // the merge can change the soundboard
+        *self = Soundboard::from_config(&json_path, config)?;
         Ok(())
     }
 
@@ -242,7 +490,19 @@ impl Soundboard {
         soundboard: &Soundboard,
         sound: &Sound,
     ) -> Result<SoundId> {
-        let new_sound = Sound::new(sound.get_name(), sound.get_source().clone())?;
+        let mut new_sound = Sound::new(sound.get_name(), sound.get_source().clone())?;
+        new_sound.set_volume(sound.get_volume())?;
+        new_sound.set_fade_in(sound.get_fade_in())?;
+        new_sound.set_fade_out(sound.get_fade_out())?;
+        new_sound.set_loop_mode(sound.get_loop_mode())?;
+        new_sound.set_pitch(sound.get_pitch())?;
+        new_sound.set_speed(sound.get_speed())?;
+        new_sound.set_reverse(sound.get_reverse());
+        new_sound.set_devices(sound.get_devices().clone());
+        new_sound.set_tags(sound.get_tags().clone());
+        new_sound.set_description(sound.get_description().clone());
+        new_sound.set_color(sound.get_color().clone())?;
+        new_sound.set_icon(sound.get_icon().clone())?;
         let new_sound_id = *new_sound.get_id();
         if let Source::Local { path } = sound.get_source() {
             let mut old_path = soundboard.get_sounds_path().unwrap();
@@ -251,9 +511,35 @@ impl Soundboard {
         } else {
             self.add_sound(new_sound)?;
         }
+        self.copy_sound_icon_from_another_soundboard(soundboard, sound)?;
         Ok(new_sound_id)
     }
 
+    /// Copies the image icon of the sound into the sounds directory of this soundboard
+    fn copy_sound_icon_from_another_soundboard(
+        &self,
+        soundboard: &Soundboard,
+        sound: &Sound,
+    ) -> Result<()> {
+        // a missing icon does not prevent copying the sound
+        let (old_path, new_path) = match (
+            soundboard.get_sound_icon_path(sound).ok().flatten(),
+            self.sound_icon_path(sound)?,
+        ) {
+            (Some(old_path), Some(new_path)) => (old_path, new_path),
+            _ => return Ok(()),
+        };
+        if new_path.exists() {
+            return Ok(());
+        }
+        if let Some(parent) = new_path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::copy(&old_path, &new_path)
+            .with_context(|| format!("cant copy icon to {}", new_path.display()))?;
+        Ok(())
+    }
+
     pub fn add_sound_with_reader<R: ?Sized>(
         &mut self,
         sound: Sound,
@@ -325,6 +611,52 @@ impl Soundboard {
         &self.id
     }
 
+    /// Takes over the ids of the soundboard and its sounds from a previous load
+    ///
+    /// Sounds are matched by their id, sounds loaded without an id are then matched by their
+    /// config, then by name and source and then by name
+    fn keep_ids_of(&mut self, old: &Soundboard) {
+        self.id = old.id;
+        let mut unmatched: Vec<&Sound> = old.iter().collect();
+        let matchers: [fn(&Sound, &Sound) -> bool; 4] = [
+            |a, b| a.id == b.id,
+            |a, b| a.config == b.config,
+            |a, b| a.get_name() == b.get_name() && a.get_source() == b.get_source(),
+            |a, b| a.get_name() == b.get_name(),
+        ];
+        let mut new_ids: Vec<Option<SoundId>> = vec![None; self.sound_positions.len()];
+        for (matcher_index, matcher) in matchers.iter().enumerate() {
+            for (index, id) in self.sound_positions.iter().enumerate() {
+                if new_ids[index].is_some() {
+                    continue;
+                }
+                let sound = &self.sounds[id];
+                // a persisted id is never replaced by the id of another sound
+                if matcher_index > 0 && !sound.loaded_without_id {
+                    continue;
+                }
+                if let Some(position) = unmatched.iter().position(|old| matcher(sound, old)) {
+                    new_ids[index] = Some(unmatched.remove(position).id);
+                }
+            }
+        }
+        let mut sounds = SoundMap::default();
+        for (position, new_id) in self.sound_positions.iter_mut().zip(new_ids) {
+            let mut sound = self.sounds.remove(position).unwrap();
+            if let Some(new_id) = new_id {
+                sound.id = new_id;
+                *position = new_id;
+            }
+            sounds.insert(sound.id, sound);
+        }
+        self.sounds = sounds;
+
+        // the merge base has to know the kept ids
+        if let Some(last_config) = self.last_config.as_mut() {
+            set_config_ids(last_config, self.id, &self.sound_positions);
+        }
+    }
+
     pub fn get_name(&self) -> &str {
         &self.name
     }
@@ -360,6 +692,59 @@ impl Soundboard {
         self.hotkey = hotkey;
     }
 
+    /// Returns the default routing of the sounds
+    pub fn get_devices(&self) -> &Option<sound::SoundDevices> {
+        &self.devices
+    }
+
+    pub fn set_devices(&mut self, devices: Option<sound::SoundDevices>) {
+        self.devices = devices;
+    }
+
+    /// Returns the routing of the sound or else the default routing of the soundboard
+    pub fn get_sound_devices(&self, sound: &Sound) -> sound::SoundDevices {
+        sound
+            .get_devices()
+            .clone()
+            .or_else(|| self.devices.clone())
+            .unwrap_or(sound::SoundDevices::Both)
+    }
+
+    /// Returns the path of the image icon of the sound in the sounds directory of the soundboard
+    ///
+    /// The file does not need to exist
+    fn sound_icon_path(&self, sound: &Sound) -> Result<Option<PathBuf>> {
+        let icon = match sound.get_icon() {
+            Some(icon) if is_image_icon(icon) => icon,
+            _ => return Ok(None),
+        };
+        validate_icon(icon)?;
+        let mut icon_path = self.get_sounds_path()?;
+        icon_path.push(icon);
+        Ok(Some(icon_path))
+    }
+
+    /// Returns the path of the existing image icon of the sound
+    ///
+    /// Fails for icons outside of the sounds directory of the soundboard
+    pub fn get_sound_icon_path(&self, sound: &Sound) -> Result<Option<PathBuf>> {
+        let icon_path = match self.sound_icon_path(sound)? {
+            Some(icon_path) => icon_path,
+            None => return Ok(None),
+        };
+        let sounds_path = self.get_sounds_path()?.canonicalize()?;
+        let icon_path = icon_path
+            .canonicalize()
+            .with_context(|| format!("icon not found {}", icon_path.display()))?;
+        if !icon_path.starts_with(&sounds_path) {
+            return Err(anyhow!(
+                "icon is outside of the sounds directory {}",
+                icon_path.display()
+            ));
+        }
+        Ok(Some(icon_path))
+    }
+
     pub fn get_hotkey_string_or_none(&self) -> Option<String> {
         if let Some(hotkey) = self.get_hotkey() {
             Some(hotkey.to_string())
@@ -419,6 +804,8 @@ pub struct Sound {
 
     hotkey: Option<hotkey::Hotkey>,
     id: ulid::Ulid,
+    /// the id was generated because the config on disk has none
+    loaded_without_id: bool,
 }
 
 impl Sound {
@@ -427,10 +814,14 @@ impl Sound {
             config: SoundConfig::new(name, source),
             hotkey: None,
             id: Ulid::new(),
+            loaded_without_id: false,
         })
     }
 
-    fn from_config(config: SoundConfig) -> Result<Self> {
+    fn from_config(mut config: SoundConfig) -> Result<Self> {
+        // the id of the sound is the only source of truth
+        let loaded_without_id = config.id.is_none();
+        let id = config.id.take().unwrap_or_else(Ulid::new);
         let hotkey = {
             if let Some(hotkey) = config.hotkey.as_ref() {
                 Some(hotkey::parse_hotkey(&hotkey)?)
@@ -441,7 +832,8 @@ impl Sound {
         Ok(Self {
             config,
             hotkey,
-            id: Ulid::new(),
+            id,
+            loaded_without_id,
         })
     }
 
@@ -521,10 +913,242 @@ impl Sound {
         self.config.end = end;
         Ok(())
     }
+
+    /// Returns the volume factor of the sound
+    ///
+    /// `None` means the sound is played unchanged
+    pub fn get_volume(&self) -> Option<f32> {
+        self.config.volume
+    }
+
+    pub fn set_volume(&mut self, volume: Option<f32>) -> Result<()> {
+        if let Some(volume) = volume {
+            if !(volume.is_finite() && volume >= 0.0) {
+                return Err(anyhow!("volume should be a positive number"));
+            }
+        }
+        self.config.volume = volume;
+        Ok(())
+    }
+
+    /// Returns the fade in duration in seconds
+    ///
+    /// `None` means the default of the app config is used
+    pub fn get_fade_in(&self) -> Option<f32> {
+        self.config.fade_in
+    }
+
+    pub fn set_fade_in(&mut self, fade_in: Option<f32>) -> Result<()> {
+        if let Some(fade_in) = fade_in {
+            if fade_in < 0.0 {
+                return Err(anyhow!("fade_in should be positive"));
+            }
+        }
+        self.config.fade_in = fade_in;
+        Ok(())
+    }
+
+    /// Returns the fade out duration in seconds which is used when the sound is stopped
+    ///
+    /// `None` means the default of the app config is used
+    pub fn get_fade_out(&self) -> Option<f32> {
+        self.config.fade_out
+    }
+
+    pub fn set_fade_out(&mut self, fade_out: Option<f32>) -> Result<()> {
+        if let Some(fade_out) = fade_out {
+            if fade_out < 0.0 {
+                return Err(anyhow!("fade_out should be positive"));
+            }
+        }
+        self.config.fade_out = fade_out;
+        Ok(())
+    }
+
+    pub fn get_loop_mode(&self) -> Option<LoopMode> {
+        self.config.loop_mode
+    }
+
+    pub fn set_loop_mode(&mut self, loop_mode: Option<LoopMode>) -> Result<()> {
+        if let Some(LoopMode::Count(0)) = loop_mode {
+            return Err(anyhow!("loop count should be at least 1"));
+        }
+        self.config.loop_mode = loop_mode;
+        Ok(())
+    }
+
+    /// Returns the pitch shift in semitones which keeps the duration
+    pub fn get_pitch(&self) -> Option<f32> {
+        self.config.pitch
+    }
+
+    pub fn set_pitch(&mut self, pitch: Option<f32>) -> Result<()> {
+        if let Some(pitch) = pitch {
+            if !(-24.0..=24.0).contains(&pitch) {
+                return Err(anyhow!("pitch should be between -24 and 24 semitones"));
+            }
+        }
+        self.config.pitch = pitch;
+        Ok(())
+    }
+
+    /// Returns the playback speed factor which changes the duration and the pitch
+    pub fn get_speed(&self) -> Option<f32> {
+        self.config.speed
+    }
+
+    pub fn set_speed(&mut self, speed: Option<f32>) -> Result<()> {
+        if let Some(speed) = speed {
+            if !(0.25..=4.0).contains(&speed) {
+                return Err(anyhow!("speed should be between 0.25 and 4"));
+            }
+        }
+        self.config.speed = speed;
+        Ok(())
+    }
+
+    pub fn get_reverse(&self) -> Option<bool> {
+        self.config.reverse
+    }
+
+    pub fn set_reverse(&mut self, reverse: Option<bool>) {
+        self.config.reverse = reverse;
+    }
+
+    /// Returns the routing of the sound which overrides the routing of the soundboard
+    pub fn get_devices(&self) -> &Option<sound::SoundDevices> {
+        &self.config.devices
+    }
+
+    pub fn set_devices(&mut self, devices: Option<sound::SoundDevices>) {
+        self.config.devices = devices;
+    }
+
+    /// Returns the tags which group the sound, e.g. for filtering
+    pub fn get_tags(&self) -> &Option<Vec<String>> {
+        &self.config.tags
+    }
+
+    /// Empty tags and duplicates are removed
+    pub fn set_tags(&mut self, tags: Option<Vec<String>>) {
+        self.config.tags = tags
+            .map(|tags| {
+                let mut unique: Vec<String> = Vec::new();
+                for tag in tags {
+                    let tag = tag.trim();
+                    if !tag.is_empty() && !unique.iter().any(|t| t == tag) {
+                        unique.push(tag.to_owned());
+                    }
+                }
+                unique
+            })
+            .filter(|tags| !tags.is_empty());
+    }
+
+    pub fn get_description(&self) -> &Option<String> {
+        &self.config.description
+    }
+
+    pub fn set_description(&mut self, description: Option<String>) {
+        self.config.description = description.filter(|d| !d.is_empty());
+    }
+
+    /// Returns the color of the sound button as hex string, e.g. `#ff8800`
+    pub fn get_color(&self) -> &Option<String> {
+        &self.config.color
+    }
+
+    pub fn set_color(&mut self, color: Option<String>) -> Result<()> {
+        if let Some(color) = color.as_ref() {
+            parse_color(color)?;
+        }
+        self.config.color = color;
+        Ok(())
+    }
+
+    /// Returns the icon of the sound, an emoji or the path of an image
+    ///
+    /// Image paths are relative to the sounds directory of the soundboard
+    pub fn get_icon(&self) -> &Option<String> {
+        &self.config.icon
+    }
+
+    pub fn set_icon(&mut self, icon: Option<String>) -> Result<()> {
+        let icon = icon.filter(|i| !i.is_empty());
+        if let Some(icon) = icon.as_ref() {
+            validate_icon(icon)?;
+        }
+        self.config.icon = icon;
+        Ok(())
+    }
+}
+
+/// File extensions of image icons
+///
+/// svg is not supported because svg files can contain scripts
+const ICON_EXTENSIONS: [&str; 6] = ["png", "jpg", "jpeg", "gif", "webp", "bmp"];
+
+/// Returns true if the icon is the path of an image instead of an emoji
+pub fn is_image_icon(icon: &str) -> bool {
+    Path::new(icon).extension().is_some()
+}
+
+/// Checks that an image icon is an image path inside of the sounds directory
+fn validate_icon(icon: &str) -> Result<()> {
+    if !is_image_icon(icon) {
+        return Ok(());
+    }
+    let path = Path::new(icon);
+    let extension = path
+        .extension()
+        .unwrap_or_default()
+        .to_string_lossy()
+        .to_lowercase();
+    if !ICON_EXTENSIONS.contains(&extension.as_str()) {
+        return Err(anyhow!(
+            "sound: icon {} is not an image, supported: {}",
+            icon,
+            ICON_EXTENSIONS.join(", ")
+        ));
+    }
+    if !path
+        .components()
+        .all(|c| matches!(c, std::path::Component::Normal(_)))
+    {
+        return Err(anyhow!(
+            "sound: icon {} must be a path relative to the sounds directory",
+            icon
+        ));
+    }
+    Ok(())
+}
+
+/// Parses a hex color like `#ff8800` or `#f80` into its red, green and blue components
+pub fn parse_color(color: &str) -> Result<(u8, u8, u8)> {
+    let hex = color
+        .strip_prefix('#')
+        .filter(|hex| hex.chars().all(|c| c.is_ascii_hexdigit()))
+        .ok_or_else(|| anyhow!("color should be a hex color like #ff8800"))?;
+    let component = |digits: &str| u8::from_str_radix(digits, 16).unwrap();
+    match hex.len() {
+        3 => {
+            let short = |i: usize| component(&hex[i..i + 1]) * 17;
+            Ok((short(0), short(1), short(2)))
+        }
+        6 => Ok((
+            component(&hex[0..2]),
+            component(&hex[2..4]),
+            component(&hex[4..6]),
+        )),
+        _ => Err(anyhow!("color should be a hex color like #ff8800")),
+    }
 }
 
 #[derive(Debug, Deserialize, Clone, Serialize, Eq, PartialEq, Hash, Default)]
 struct SoundboardConfig {
+    /// generated on the first save so the id is stable across restarts
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub id: Option<SoundboardId>,
     pub name: String,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub hotkey: Option<String>,
@@ -532,6 +1156,8 @@ struct SoundboardConfig {
     pub position: Option<usize>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub disabled: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub devices: Option<sound::SoundDevices>,
     #[serde(rename = "sound")]
     #[serde(skip_serializing_if = "Option::is_none")]
     pub sounds: Option<Vec<SoundConfig>>,
@@ -548,6 +1174,9 @@ impl SoundboardConfig {
 
 #[derive(Debug, Deserialize, Clone, Serialize)]
 struct SoundConfig {
+    /// generated on the first save so the id is stable across restarts
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub id: Option<SoundId>,
     pub name: String,
     pub source: Source,
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -556,6 +1185,52 @@ struct SoundConfig {
     pub start: Option<f32>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub end: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub volume: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fade_in: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fade_out: Option<f32>,
+    #[serde(rename = "loop")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub loop_mode: Option<LoopMode>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pitch: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub speed: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reverse: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub devices: Option<sound::SoundDevices>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tags: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub color: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub icon: Option<String>,
+}
+
+/// Loop mode of a sound
+///
+/// `loop = true` repeats the sound until it is stopped, `loop = 3` plays the sound three times
+#[derive(Debug, Deserialize, Clone, Copy, Serialize, PartialEq, Eq, Hash)]
+#[serde(untagged)]
+pub enum LoopMode {
+    Infinite(bool),
+    Count(u32),
+}
+
+impl LoopMode {
+    /// Returns how often the sound is played, `None` means until it is stopped
+    pub fn plays(&self) -> Option<u32> {
+        match self {
+            LoopMode::Infinite(true) => None,
+            LoopMode::Infinite(false) => Some(1),
+            LoopMode::Count(count) => Some(*count),
+        }
+    }
 }
 
 #[derive(Debug, Deserialize, Clone, Serialize, PartialEq, Eq, Hash)]
@@ -585,11 +1260,24 @@ pub struct HeaderConfig {
 impl SoundConfig {
     pub fn new(name: &str, source: Source) -> Self {
         Self {
+            id: None,
             name: name.to_string(),
             source,
             hotkey: None,
             start: None,
             end: None,
+            volume: None,
+            fade_in: None,
+            fade_out: None,
+            loop_mode: None,
+            pitch: None,
+            speed: None,
+            reverse: None,
+            devices: None,
+            tags: None,
+            description: None,
+            color: None,
+            icon: None,
         }
     }
 
@@ -602,35 +1290,77 @@ impl SoundConfig {
             }
         };
         Self {
+            id: Some(*sound.get_id()),
             name: sound.get_name().to_string(),
             source: sound.get_source().clone(),
             hotkey,
             start: sound.get_start(),
             end: sound.get_end(),
+            volume: sound.get_volume(),
+            fade_in: sound.get_fade_in(),
+            fade_out: sound.get_fade_out(),
+            loop_mode: sound.get_loop_mode(),
+            pitch: sound.get_pitch(),
+            speed: sound.get_speed(),
+            reverse: sound.get_reverse(),
+            devices: sound.get_devices().clone(),
+            tags: sound.get_tags().clone(),
+            description: sound.get_description().clone(),
+            color: sound.get_color().clone(),
+            icon: sound.get_icon().clone(),
         }
     }
 }
 
 impl PartialEq for SoundConfig {
     fn eq(&self, other: &Self) -> bool {
-        self.name == other.name
+        self.id == other.id
+            && self.name == other.name
             && self.hotkey == other.hotkey
             && self.source == other.source
             && ((self.start.unwrap_or_default() * 10.0) as usize)
                 == ((other.start.unwrap_or_default() * 10.0) as usize)
             && ((self.end.unwrap_or_default() * 10.0) as usize)
                 == ((other.end.unwrap_or_default() * 10.0) as usize)
+            && self.volume.map(|v| (v * 100.0) as usize)
+                == other.volume.map(|v| (v * 100.0) as usize)
+            && self.fade_in.map(|v| (v * 100.0) as usize)
+                == other.fade_in.map(|v| (v * 100.0) as usize)
+            && self.fade_out.map(|v| (v * 100.0) as usize)
+                == other.fade_out.map(|v| (v * 100.0) as usize)
+            && self.loop_mode == other.loop_mode
+            && self.pitch.map(|v| (v * 100.0) as isize) == other.pitch.map(|v| (v * 100.0) as isize)
+            && self.speed.map(|v| (v * 100.0) as usize) == other.speed.map(|v| (v * 100.0) as usize)
+            && self.reverse == other.reverse
+            && self.devices == other.devices
+            && self.tags == other.tags
+            && self.description == other.description
+            && self.color == other.color
+            && self.icon == other.icon
     }
 }
 impl Eq for SoundConfig {}
 
 impl Hash for SoundConfig {
     fn hash<H: Hasher>(&self, state: &mut H) {
+        self.id.hash(state);
         self.name.hash(state);
         self.source.hash(state);
         self.hotkey.hash(state);
         ((self.start.unwrap_or_default() * 10.0) as usize).hash(state);
         ((self.end.unwrap_or_default() * 10.0) as usize).hash(state);
+        self.volume.map(|v| (v * 100.0) as usize).hash(state);
+        self.fade_in.map(|v| (v * 100.0) as usize).hash(state);
+        self.fade_out.map(|v| (v * 100.0) as usize).hash(state);
+        self.loop_mode.hash(state);
+        self.pitch.map(|v| (v * 100.0) as isize).hash(state);
+        self.speed.map(|v| (v * 100.0) as usize).hash(state);
+        self.reverse.hash(state);
+        self.devices.hash(state);
+        self.tags.hash(state);
+        self.description.hash(state);
+        self.color.hash(state);
+        self.icon.hash(state);
     }
 }
 
@@ -695,38 +1425,170 @@ fn load_soundboard_config(soundboard_path: &Path) -> Result<SoundboardConfig> {
     Ok(soundboard_config)
 }
 
-fn check_soundboard_config_mutated_on_disk(path: &Path, last_hash: u64) -> Result<bool> {
-    let file_str = fs::read_to_string(&path)
-        .with_context(|| format!("Failed to read_to_string {}", path.display()))?;
+/// Changes of a save which clash with changes of the soundboard file on disk
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MergeConflict {
+    /// settings of the soundboard changed on disk and by the save
+    pub fields: Vec<String>,
+    /// sounds changed on disk and by the save
+    pub sounds: Vec<(SoundId, String)>,
+}
 
-    let soundboard_config: SoundboardConfig;
-    if PathBuf::from(path)
-        .extension()
-        .unwrap_or_default()
-        .to_string_lossy()
-        == "toml"
-    {
-        soundboard_config = toml::from_str(&file_str)
-            .with_context(|| format!("Failed to parse {}", path.display()))?;
+impl fmt::Display for MergeConflict {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "soundboard changed on disk, conflicting")?;
+        if !self.fields.is_empty() {
+            write!(f, " settings: {}", self.fields.join(", "))?;
+        }
+        if !self.sounds.is_empty() {
+            let names: Vec<&str> = self.sounds.iter().map(|(_, name)| name.as_str()).collect();
+            write!(f, " sounds: {}", names.join(", "))?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for MergeConflict {}
+
+/// Sets the ids of a soundboard config and its sounds in the order of the sounds
+fn set_config_ids(config: &mut SoundboardConfig, id: SoundboardId, sound_ids: &[SoundId]) {
+    config.id = Some(id);
+    for (sound_config, sound_id) in config.sounds.iter_mut().flatten().zip(sound_ids.iter()) {
+        sound_config.id = Some(*sound_id);
+    }
+}
+
+/// Gives the sounds of a config from disk without an id the id of the equal sound of the base
+///
+/// Sounds without an equal sound in the base were added on disk and get a new id
+fn assign_ids_from_base(config: &mut SoundboardConfig, base: &SoundboardConfig) {
+    if config.id.is_none() {
+        config.id = base.id;
+    }
+    let mut unmatched: Vec<&SoundConfig> = base.sounds.iter().flatten().collect();
+    for sound_config in config.sounds.iter_mut().flatten() {
+        if let Some(id) = sound_config.id {
+            unmatched.retain(|base_sound| base_sound.id != Some(id));
+            continue;
+        }
+        let position = unmatched.iter().position(|base_sound| {
+            SoundConfig {
+                id: None,
+                ..(*base_sound).clone()
+            } == *sound_config
+        });
+        sound_config.id = match position {
+            Some(position) => unmatched.remove(position).id,
+            None => Some(Ulid::new()),
+        };
+    }
+}
+
+/// Returns the side which changed the value or `None` if both changed it differently
+fn merge_value<T: PartialEq + Clone>(base: &T, ours: &T, theirs: &T) -> Option<T> {
+    if ours == base || ours == theirs {
+        Some(theirs.clone())
+    } else if theirs == base {
+        Some(ours.clone())
     } else {
-        soundboard_config = serde_json::from_str(&file_str)
-            .with_context(|| format!("Failed to parse {}", path.display()))?;
+        None
     }
+}
 
-    let old_config_hash = utils::calculate_hash(&soundboard_config);
+/// Three way merge of the soundboard settings and the sounds by their id
+///
+/// All sound configs need an id
+fn merge_soundboard_configs(
+    base: &SoundboardConfig,
+    ours: &SoundboardConfig,
+    theirs: &SoundboardConfig,
+) -> std::result::Result<SoundboardConfig, MergeConflict> {
+    let mut conflict = MergeConflict {
+        fields: Vec::new(),
+        sounds: Vec::new(),
+    };
+    let mut merged = SoundboardConfig::new(&ours.name);
+    merged.id = ours.id;
+
+    macro_rules! merge_field {
+        ($field:ident) => {
+            match merge_value(&base.$field, &ours.$field, &theirs.$field) {
+                Some(value) => merged.$field = value,
+                None => conflict.fields.push(stringify!($field).to_owned()),
+            }
+        };
+    }
+    merge_field!(name);
+    merge_field!(hotkey);
+    merge_field!(position);
+    merge_field!(disabled);
+    merge_field!(devices);
+
+    let sounds_by_id = |config: &SoundboardConfig| -> HashMap<SoundId, SoundConfig> {
+        config
+            .sounds
+            .iter()
+            .flatten()
+            .filter_map(|sound| sound.id.map(|id| (id, sound.clone())))
+            .collect()
+    };
+    let order = |config: &SoundboardConfig| -> Vec<SoundId> {
+        config
+            .sounds
+            .iter()
+            .flatten()
+            .filter_map(|sound| sound.id)
+            .collect()
+    };
+    let (base_sounds, our_sounds, their_sounds) =
+        (sounds_by_id(base), sounds_by_id(ours), sounds_by_id(theirs));
+
+    // the order of the side which reordered the sounds wins, new sounds of the other side are appended
+    let (first_order, second_order) = if order(ours) == order(base) {
+        (order(theirs), order(ours))
+    } else {
+        (order(ours), order(theirs))
+    };
+    let mut ids = first_order;
+    for id in second_order {
+        if !ids.contains(&id) {
+            ids.push(id);
+        }
+    }
 
-    if old_config_hash == last_hash {
-        return Ok(false);
+    let mut sounds = Vec::new();
+    for id in ids {
+        let (base_sound, our_sound, their_sound) = (
+            base_sounds.get(&id).cloned(),
+            our_sounds.get(&id).cloned(),
+            their_sounds.get(&id).cloned(),
+        );
+        match merge_value(&base_sound, &our_sound, &their_sound) {
+            Some(Some(sound)) => sounds.push(sound),
+            Some(None) => {}
+            None => {
+                let name = our_sound.or(their_sound).map(|sound| sound.name);
+                conflict.sounds.push((id, name.unwrap_or_default()));
+            }
+        }
     }
+    merged.sounds = Some(sounds);
 
-    Ok(true)
+    if conflict.fields.is_empty() && conflict.sounds.is_empty() {
+        Ok(merged)
+    } else {
+        Err(conflict)
+    }
 }
 
+/// Writes the config as json, returns the path of the json file
+///
+/// `exists_on_disk` is false for new soundboards which must not overwrite a file
 fn save_soundboard_config(
     soundboard_config_path: &Path,
     config: &SoundboardConfig,
-    last_hash: Option<u64>,
-) -> Result<()> {
+    exists_on_disk: bool,
+) -> Result<PathBuf> {
     if soundboard_config_path.parent().is_none()
         || !soundboard_config_path.parent().unwrap().exists()
     {
@@ -736,13 +1598,7 @@ fn save_soundboard_config(
         ));
     }
 
-    if last_hash.is_some()
-        && check_soundboard_config_mutated_on_disk(&soundboard_config_path, last_hash.unwrap())?
-    {
-        return Err(anyhow!(
-            "save_soundboard: soundboard config file mutated on disk",
-        ));
-    } else if last_hash.is_none() && soundboard_config_path.exists() {
+    if !exists_on_disk && soundboard_config_path.exists() {
         return Err(anyhow!(
             "save_soundboard: soundboard config file already exists on disk",
         ));
@@ -762,11 +1618,10 @@ fn save_soundboard_config(
             .to_string_lossy()
             == "toml"
     {
-        fs::rename(
-            soundboard_config_path,
-            PathBuf::from(soundboard_config_path).with_extension("toml_disabled_oldformat"),
-        )
-        .unwrap();
+        let disabled_path =
+            PathBuf::from(soundboard_config_path).with_extension("toml_disabled_oldformat");
+        fs::rename(soundboard_config_path, &disabled_path)
+            .with_context(|| format!("Failed to rename to {}", disabled_path.display()))?;
     }
 
     // let pretty_string =
@@ -778,5 +1633,134 @@ fn save_soundboard_config(
         "Saved config file at {}",
         soundboard_json_path.to_str().unwrap()
     );
-    Ok(())
+    Ok(soundboard_json_path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sound_config(name: &str) -> SoundConfig {
+        let mut config = SoundConfig::new(
+            name,
+            Source::Local {
+                path: format!("{}.mp3", name),
+            },
+        );
+        config.id = Some(Ulid::new());
+        config
+    }
+
+    #[test]
+    fn parse_color_formats() {
+        assert_eq!(parse_color("#ff8800").unwrap(), (255, 136, 0));
+        assert_eq!(parse_color("#F80").unwrap(), (255, 136, 0));
+        assert!(parse_color("ff8800").is_err());
+        assert!(parse_color("#ff880").is_err());
+        assert!(parse_color("#gg8800").is_err());
+    }
+
+    #[test]
+    fn validate_icon_paths() {
+        assert!(validate_icon("🔊").is_ok());
+        assert!(validate_icon("icons/horn.png").is_ok());
+        assert!(validate_icon("horn.PNG").is_ok());
+        assert!(validate_icon("/home/user/.ssh/id_rsa.png").is_err());
+        assert!(validate_icon("../../secret.png").is_err());
+        assert!(validate_icon("icons/../horn.png").is_err());
+        assert!(validate_icon("notes.txt").is_err());
+        assert!(validate_icon("horn.svg").is_err());
+    }
+
+    #[test]
+    fn keep_ids_of_persisted_and_generated_ids() {
+        let mut old_config = SoundboardConfig::new("board");
+        old_config.id = Some(Ulid::new());
+        old_config.sounds = Some(vec![sound_config("a"), sound_config("b")]);
+        let old = Soundboard::from_config(Path::new("board.json"), old_config.clone()).unwrap();
+
+        let mut config = SoundboardConfig::new("board");
+        config.sounds = Some(vec![sound_config("a"), sound_config("b")]);
+        config.sounds.as_mut().unwrap()[1].id = None;
+        let persisted_id = config.sounds.as_ref().unwrap()[0].id.unwrap();
+        let mut soundboard = Soundboard::from_config(Path::new("board.json"), config).unwrap();
+        soundboard.keep_ids_of(&old);
+
+        let old_ids: Vec<SoundId> = old_config
+            .sounds
+            .unwrap()
+            .iter()
+            .map(|s| s.id.unwrap())
+            .collect();
+        assert_eq!(soundboard.id, old.id);
+        assert_eq!(soundboard.sound_positions, vec![persisted_id, old_ids[1]]);
+        let last_config = soundboard.last_config.unwrap();
+        assert_eq!(last_config.id, Some(old.id));
+        assert_eq!(
+            last_config
+                .sounds
+                .unwrap()
+                .iter()
+                .map(|s| s.id.unwrap())
+                .collect::<Vec<_>>(),
+            soundboard.sound_positions
+        );
+    }
+
+    #[test]
+    fn history_record_is_bounded() {
+        let mut history = History::default();
+        history.redo.push(SoundboardConfig::new("redo"));
+        for i in 0..HISTORY_LIMIT + 2 {
+            history.record(SoundboardConfig::new(&i.to_string()));
+        }
+        assert!(history.redo.is_empty());
+        assert_eq!(history.undo.len(), HISTORY_LIMIT);
+        assert_eq!(history.undo[0].name, "2");
+        assert_eq!(
+            history.undo.last().unwrap().name,
+            (HISTORY_LIMIT + 1).to_string()
+        );
+    }
+
+    #[test]
+    fn merge_soundboard_configs_changes() {
+        let mut base = SoundboardConfig::new("board");
+        base.sounds = Some(vec![sound_config("a"), sound_config("b")]);
+
+        let mut ours = base.clone();
+        ours.sounds.as_mut().unwrap()[0].volume = Some(0.5);
+        let mut theirs = base.clone();
+        theirs.hotkey = Some("CTRL-P".to_owned());
+        theirs.sounds.as_mut().unwrap().remove(1);
+        theirs.sounds.as_mut().unwrap().push(sound_config("c"));
+
+        let merged = merge_soundboard_configs(&base, &ours, &theirs).unwrap();
+        assert_eq!(merged.hotkey, Some("CTRL-P".to_owned()));
+        let sounds = merged.sounds.unwrap();
+        assert_eq!(
+            sounds.iter().map(|s| s.name.as_str()).collect::<Vec<_>>(),
+            vec!["a", "c"]
+        );
+        assert_eq!(sounds[0].volume, Some(0.5));
+    }
+
+    #[test]
+    fn merge_soundboard_configs_conflict() {
+        let mut base = SoundboardConfig::new("board");
+        base.sounds = Some(vec![sound_config("a"), sound_config("b")]);
+
+        let mut ours = base.clone();
+        ours.sounds.as_mut().unwrap()[1].volume = Some(0.5);
+        let mut theirs = base.clone();
+        theirs.sounds.as_mut().unwrap()[1].volume = Some(0.8);
+        theirs.sounds.as_mut().unwrap()[0].volume = Some(0.2);
+
+        let conflict = merge_soundboard_configs(&base, &ours, &theirs).unwrap_err();
+        assert!(conflict.fields.is_empty());
+        assert_eq!(
+            conflict.sounds,
+            vec![(base.sounds.unwrap()[1].id.unwrap(), "b".to_owned())]
+        );
+    }
 }
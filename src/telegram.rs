@@ -34,6 +34,8 @@ enum MethodType {
     Download = 0,
     #[serde(rename(serialize = "p", deserialize = "p"))]
     Play = 1,
+    #[serde(rename(serialize = "q", deserialize = "q"))]
+    Enqueue = 2,
 }
 
 #[derive(Deserialize, Serialize)]
@@ -73,10 +75,32 @@ async fn download_file(api: &Api, file_id: &str, file_name: &str) -> Result<Path
 fn play_sound(sender: &Sender<sound::Message>, sound_id: soundboards::SoundId) -> Result<()> {
     Ok(sender.send(sound::Message::PlaySound(
         sound_id,
-        sound::SoundDevices::Both,
+        soundboards::find_sound_devices(sound_id),
     ))?)
 }
 
+fn enqueue_sound(sender: &Sender<sound::Message>, sound_id: soundboards::SoundId) -> Result<()> {
+    Ok(sender.send(sound::Message::Enqueue(
+        sound_id,
+        soundboards::find_sound_devices(sound_id),
+    ))?)
+}
+
+/// Returns the queued sounds of all queues with their devices and position in their queue
+fn queued_sounds() -> Vec<(sound::SoundDevices, usize, soundboards::SoundId)> {
+    let mut queues: Vec<_> = sound::get_queue().into_iter().collect();
+    queues.sort_by(|(a, _), (b, _)| a.cmp(b));
+    queues
+        .into_iter()
+        .flat_map(|(sound_devices, queue)| {
+            queue
+                .into_iter()
+                .enumerate()
+                .map(move |(index, sound_id)| (sound_devices.clone(), index, sound_id))
+        })
+        .collect()
+}
+
 fn send_new_sound_config(
     sender: &Sender<sound::Message>,
     name: String,
@@ -219,6 +243,74 @@ async fn handle_stopall_command(
         .expect("sound channel error");
 }
 
+async fn handle_queue_command(api: &Api, message: &Message) {
+    let queue = queued_sounds();
+
+    let text = if queue.is_empty() {
+        "The queue is empty".to_string()
+    } else {
+        queue.iter().enumerate().fold(
+            "Up next:".to_string(),
+            |acc, (index, (sound_devices, _, sound_id))| {
+                let name = soundboards::find_sound(*sound_id)
+                    .map(|sound| sound.get_name().to_string())
+                    .unwrap_or_else(|| sound_id.to_string());
+                if *sound_devices == sound::SoundDevices::Both {
+                    format!("{}\n {}. {}", acc, index + 1, name)
+                } else {
+                    format!("{}\n {}. {} ({})", acc, index + 1, name, sound_devices)
+                }
+            },
+        )
+    };
+    let method = SendMessage::new(message.get_chat_id(), text);
+    if let Err(err) = api.execute(method).await {
+        error!("telegram api error: {}", err);
+    }
+}
+
+async fn handle_queuemove_command(
+    api: &Api,
+    sender: &Sender<sound::Message>,
+    message: &Message,
+    raw_args: String,
+) {
+    let queue = queued_sounds();
+    let positions: Vec<usize> = raw_args
+        .split_whitespace()
+        .filter_map(|arg| arg.parse().ok())
+        .collect();
+
+    if let [from, to] = positions[..] {
+        if from >= 1 && to >= 1 && from <= queue.len() && to <= queue.len() {
+            let (from_devices, from_index, _) = &queue[from - 1];
+            let (to_devices, to_index, _) = &queue[to - 1];
+            // sounds can only be moved within the queue of their devices
+            if from_devices == to_devices {
+                sender
+                    .send(sound::Message::MoveInQueue(
+                        from_devices.clone(),
+                        *from_index,
+                        *to_index,
+                    ))
+                    .expect("sound channel error");
+                return;
+            }
+        }
+    }
+
+    let method = SendMessage::new(
+        message.get_chat_id(),
+        format!(
+            "You need to specify two queue positions of sounds with the same devices between 1 and {} after /queuemove !",
+            queue.len()
+        ),
+    );
+    if let Err(err) = api.execute(method).await {
+        error!("telegram api error: {}", err);
+    }
+}
+
 async fn handle_sound_command(
     api: &Api,
     _sender: &Sender<sound::Message>,
@@ -228,12 +320,10 @@ async fn handle_sound_command(
 ) {
     info!("handle_sound_command arg: {}", raw_args);
 
-    let method_name = {
-        if method == MethodType::Play {
-            "/play"
-        } else {
-            "/download"
-        }
+    let method_name = match method {
+        MethodType::Download => "/download",
+        MethodType::Play => "/play",
+        MethodType::Enqueue => "/enqueue",
     };
 
     if raw_args.is_empty() {
@@ -321,6 +411,17 @@ fn play_sound_with_name(sender: &Sender<sound::Message>, name: &str) {
     }
 }
 
+fn enqueue_sound_with_name(sender: &Sender<sound::Message>, name: &str) {
+    for soundboard in soundboards::get_soundboards().values() {
+        for sound in soundboard.get_sounds().values() {
+            if sound.get_name() == name {
+                enqueue_sound(sender, *sound.get_id()).expect("sound channel error");
+                return;
+            }
+        }
+    }
+}
+
 async fn send_sound_with_name(api: &Api, message: Message, name: &str) -> Result<()> {
     let mut maybe_sound = None;
     for soundboard in soundboards::get_soundboards().values() {
@@ -410,6 +511,14 @@ impl UpdateHandler for Handler {
                                 error!("telegram api error: {}", err);
                             }
                         }
+                        MethodType::Enqueue => {
+                            enqueue_sound_with_name(&self.sender, &data.sound_name);
+                            let method = tgbot::methods::AnswerCallbackQuery::new(query.id)
+                                .text(format!("Queued sound: {}", &data.sound_name));
+                            if let Err(err) = self.api.execute(method).await {
+                                error!("telegram api error: {}", err);
+                            }
+                        }
                     }
                 }
             }
@@ -453,6 +562,42 @@ impl UpdateHandler for Handler {
                             )
                             .await;
                         }
+                        "/enqueue" => {
+                            handle_sound_command(
+                                &self.api,
+                                &self.sender,
+                                command.get_message(),
+                                raw_args,
+                                MethodType::Enqueue,
+                            )
+                            .await;
+                        }
+                        "/queue" => {
+                            handle_queue_command(&self.api, command.get_message()).await;
+                        }
+                        "/skip" => {
+                            for sound_devices in sound::get_queue().keys() {
+                                self.sender
+                                    .send(sound::Message::Skip(sound_devices.clone()))
+                                    .expect("sound channel error");
+                            }
+                        }
+                        "/clearqueue" => {
+                            for sound_devices in sound::get_queue().keys() {
+                                self.sender
+                                    .send(sound::Message::ClearQueue(sound_devices.clone()))
+                                    .expect("sound channel error");
+                            }
+                        }
+                        "/queuemove" => {
+                            handle_queuemove_command(
+                                &self.api,
+                                &self.sender,
+                                command.get_message(),
+                                raw_args,
+                            )
+                            .await;
+                        }
                         "/stopall" => {
                             handle_stopall_command(
                                 &self.api,
@@ -534,6 +679,16 @@ pub async fn run(sender: Sender<sound::Message>, receiver: Receiver<sound::Messa
         )
         .unwrap(),
         tgbot::types::BotCommand::new("stopall", "stop all sounds playing").unwrap(),
+        tgbot::types::BotCommand::new("enqueue", "queue the sound with the provided name (fuzzy)")
+            .unwrap(),
+        tgbot::types::BotCommand::new("queue", "show the upcoming sounds").unwrap(),
+        tgbot::types::BotCommand::new("skip", "skip the currently playing queued sound").unwrap(),
+        tgbot::types::BotCommand::new("clearqueue", "remove all upcoming sounds").unwrap(),
+        tgbot::types::BotCommand::new(
+            "queuemove",
+            "move a queued sound from one position to another",
+        )
+        .unwrap(),
     ]))
     .await
     .expect("SetMyCommands failed");
@@ -82,7 +82,7 @@ fn register_hotkeys<'a>(
             let _result = hotkey_manager.register(hotkey.clone(), move || {
                 if let Err(err) = tx_clone.send(sound::Message::PlaySound(
                     *sound.get_id(),
-                    sound::SoundDevices::Both,
+                    soundboards::find_sound_devices(*sound.get_id()),
                 )) {
                     error!("failed to play sound {}", err);
                 };
@@ -155,6 +155,43 @@ enum TUIEvent<I> {
 }
 
 const TICK_RATE_MS: u64 = 100;
+const VOLUME_STEP: f32 = 0.05;
+const VOLUME_BAR_WIDTH: usize = 10;
+
+fn volume_bar(volume: f32) -> String {
+    let filled = ((volume * VOLUME_BAR_WIDTH as f32).round() as usize).min(VOLUME_BAR_WIDTH);
+    format!(
+        "[{}{}] {:>3.0}%",
+        "#".repeat(filled),
+        "-".repeat(VOLUME_BAR_WIDTH - filled),
+        volume * 100.0
+    )
+}
+
+/// Returns the device after `current` in the device list, `None` is the default device
+fn next_device(
+    devices: &[sound::AudioDevice],
+    device_type: sound::AudioDeviceType,
+    current: &Option<String>,
+    with_default: bool,
+) -> Option<String> {
+    let mut choices: Vec<Option<String>> = if with_default { vec![None] } else { Vec::new() };
+    choices.extend(
+        devices
+            .iter()
+            .filter(|d| d.device_type == device_type)
+            .map(|d| Some(d.name.clone())),
+    );
+    let index = choices
+        .iter()
+        .position(|c| c == current)
+        .map_or(0, |i| i + 1);
+    choices
+        .get(index)
+        .or_else(|| choices.first())
+        .cloned()
+        .unwrap_or_else(|| current.clone())
+}
 
 pub fn draw_terminal(
     gui_sender: crossbeam_channel::Sender<sound::Message>,
@@ -169,7 +206,12 @@ pub fn draw_terminal(
 
     let mut soundboard_state = SoundboardState::new(gui_sender.clone());
     let mut active_sounds: sound::PlayStatusVecType = sound::PlayStatusVecType::new();
-    let mut current_volume = 1.0;
+    let mut current_volumes = sound::Volumes::default();
+    let audio_devices = sound::list_devices().unwrap_or_else(|err| {
+        error!("failed to list devices {:#}", err);
+        Vec::new()
+    });
+    let mut current_devices = sound::get_device_status().devices;
 
     // Setup input handling
     let (tui_sender, tui_receiver) = crossbeam_channel::unbounded();
@@ -276,7 +318,7 @@ pub fn draw_terminal(
                 .highlight_style(Style::default().bg(Color::LightGreen));
 
             let sidebar_chunks = Layout::default()
-                .constraints([Constraint::Percentage(80), Constraint::Percentage(20)].as_ref())
+                .constraints([Constraint::Min(0), Constraint::Length(10)].as_ref())
                 .direction(Direction::Vertical)
                 .margin(0)
                 .split(horizontal_chunks[1]);
@@ -290,19 +332,36 @@ pub fn draw_terminal(
                         if s.0 == sound::SoundStatus::Downloading {
                             return format!("{}\n  downloading", sound.get_name());
                         }
+                        let mut loops = {
+                            if s.4 > 0 {
+                                format!(" loop {}", s.4)
+                            } else {
+                                String::new()
+                            }
+                        };
+                        if s.0 == sound::SoundStatus::Paused {
+                            loops.push_str(" paused");
+                        }
                         if let Some(dur) = s.3 {
                             let total_seconds = dur.as_secs() % 60;
                             let total_minutes = (dur.as_secs() / 60) % 60;
                             format!(
-                                "{}\n  {}:{}/{}:{}",
+                                "{}\n  {}:{}/{}:{}{}",
                                 sound.get_name(),
                                 play_minutes,
                                 play_seconds,
                                 total_minutes,
-                                total_seconds
+                                total_seconds,
+                                loops
                             )
                         } else {
-                            format!("{}\n  {}:{}", sound.get_name(), play_minutes, play_seconds)
+                            format!(
+                                "{}\n  {}:{}{}",
+                                sound.get_name(),
+                                play_minutes,
+                                play_seconds,
+                                loops
+                            )
                         }
                     } else if s.0 == sound::SoundStatus::Downloading {
                         "<error: unknown id>\n  downloading".to_string()
@@ -326,14 +385,42 @@ pub fn draw_terminal(
             .start_corner(Corner::TopLeft)
             .style(Style::default().fg(Color::White));
 
-            let volume_string = format!("volume: {}", current_volume);
+            let volume_strings: Vec<String> = [
+                ("output", current_volumes.output),
+                ("loopback", current_volumes.loopback),
+                ("input", current_volumes.input),
+            ]
+            .iter()
+            .map(|(name, volume)| format!("{} volume: {}", name, volume_bar(*volume)))
+            .collect();
+            let device_strings: Vec<String> = [
+                ("1", "input", &current_devices.input),
+                ("2", "output", &current_devices.output),
+                ("3", "loopback", &current_devices.loopback),
+            ]
+            .iter()
+            .map(|(key, name, device)| {
+                format!(
+                    "[{}] {} device: {}",
+                    key,
+                    name,
+                    device.as_deref().unwrap_or("default")
+                )
+            })
+            .collect();
             let filter_mode_string = format!("filter_mode: {}", filter_input_mode);
             let filter_string = format!("filter: {}", current_filter);
-            let settings_list = List::new(vec![
-                ListItem::new(volume_string.as_str()),
-                ListItem::new(filter_mode_string.as_str()),
-                ListItem::new(filter_string.as_str()),
-            ])
+            let settings_list = List::new(
+                volume_strings
+                    .iter()
+                    .chain(device_strings.iter())
+                    .map(|s| ListItem::new(s.as_str()))
+                    .chain(vec![
+                        ListItem::new(filter_mode_string.as_str()),
+                        ListItem::new(filter_string.as_str()),
+                    ])
+                    .collect::<Vec<ListItem>>(),
+            )
             .block(Block::default().title("settings").borders(Borders::ALL))
             .start_corner(Corner::TopLeft)
             .style(Style::default().fg(Color::White));
@@ -351,11 +438,11 @@ pub fn draw_terminal(
         match tui_receiver.recv()? {
             TUIEvent::Tick => {
                 gui_sender
-                    .send(sound::Message::PlayStatus(Vec::new(), 0.0))
+                    .send(sound::Message::PlayStatus(Vec::new(), Default::default()))
                     .unwrap();
-                if let Ok(sound::Message::PlayStatus(sounds, volume)) = gui_receiver.recv() {
+                if let Ok(sound::Message::PlayStatus(sounds, volumes)) = gui_receiver.recv() {
                     active_sounds = sounds;
-                    current_volume = volume;
+                    current_volumes = volumes;
                 } else {
                     panic!("could not get active play status");
                 }
@@ -394,6 +481,70 @@ pub fn draw_terminal(
                                 error!("failed to send stop message {}", err);
                             };
                         }
+                        KeyCode::Char(c @ 'o')
+                        | KeyCode::Char(c @ 'O')
+                        | KeyCode::Char(c @ 'l')
+                        | KeyCode::Char(c @ 'L')
+                        | KeyCode::Char(c @ 'i')
+                        | KeyCode::Char(c @ 'I') => {
+                            let target = match c.to_ascii_lowercase() {
+                                'o' => sound::VolumeTarget::Output,
+                                'l' => sound::VolumeTarget::Loopback,
+                                _ => sound::VolumeTarget::Input,
+                            };
+                            // lowercase lowers and uppercase raises the volume
+                            let step = if c.is_ascii_uppercase() {
+                                VOLUME_STEP
+                            } else {
+                                -VOLUME_STEP
+                            };
+                            let volume = (current_volumes.get(target) + step).max(0.0).min(1.0);
+                            current_volumes.set(target, volume);
+                            if let Err(err) =
+                                gui_sender.send(sound::Message::SetVolume(target, volume))
+                            {
+                                error!("failed to send volume message {}", err);
+                            };
+                        }
+                        KeyCode::Char(c @ '1')
+                        | KeyCode::Char(c @ '2')
+                        | KeyCode::Char(c @ '3') => {
+                            let mut devices = current_devices.clone();
+                            match c {
+                                '1' => {
+                                    devices.input = next_device(
+                                        &audio_devices,
+                                        sound::AudioDeviceType::Input,
+                                        &devices.input,
+                                        true,
+                                    )
+                                }
+                                '2' => {
+                                    devices.output = next_device(
+                                        &audio_devices,
+                                        sound::AudioDeviceType::Output,
+                                        &devices.output,
+                                        true,
+                                    )
+                                }
+                                _ => {
+                                    devices.loopback = next_device(
+                                        &audio_devices,
+                                        sound::AudioDeviceType::Output,
+                                        &devices.loopback,
+                                        false,
+                                    )
+                                }
+                            }
+                            if devices != current_devices {
+                                current_devices = devices.clone();
+                                if let Err(err) =
+                                    gui_sender.send(sound::Message::SwitchDevices(devices))
+                                {
+                                    error!("failed to send switch devices message {}", err);
+                                };
+                            }
+                        }
                         KeyCode::Right | KeyCode::Char('d') => {
                             let sb_count = soundboards::get_soundboards().len();
                             if soundboard_state.get_index() + 1 == sb_count {
@@ -410,6 +561,23 @@ pub fn draw_terminal(
                                 soundboard_state.set_index(soundboard_state.get_index() - 1);
                             }
                         }
+                        KeyCode::Char(c @ 'z') | KeyCode::Char(c @ 'y') => {
+                            let index = soundboard_state.get_index();
+                            let id = soundboard_state.soundboards[index].1;
+                            let result = if c == 'z' {
+                                soundboards::undo_soundboard(id)
+                            } else {
+                                soundboards::redo_soundboard(id)
+                            };
+                            match result {
+                                Ok(()) => {
+                                    // the edit may have renamed the soundboard
+                                    soundboard_state.reload_soundboards();
+                                    soundboard_state.set_index(index);
+                                }
+                                Err(err) => warn!("failed to restore soundboard {:#}", err),
+                            }
+                        }
                         KeyCode::Down | KeyCode::Char('s') => {
                             soundboard_state.sound_state_list.next();
                         }
@@ -424,7 +592,7 @@ pub fn draw_terminal(
                                 .get_id();
                             if let Err(err) = gui_sender.send(sound::Message::PlaySound(
                                 *sound_id,
-                                sound::SoundDevices::Both,
+                                soundboards::find_sound_devices(*sound_id),
                             )) {
                                 error!("failed to send play message {}", err);
                             };
@@ -5,16 +5,28 @@ MobileDragDrop.polyfill({
 });
 
 const ModalForm = {
-  props: ['initialName', 'initialHotkey', 'initialSource'],
+  props: ['initialName', 'initialHotkey', 'initialSource', 'initialVolume', 'initialFadeIn', 'initialFadeOut', 'initialPlays', 'initialPitch', 'initialSpeed', 'initialReverse', 'initialDevices', 'initialTags', 'initialDescription', 'initialColor', 'initialIcon', 'exportUrl'],
   data: function () {
     return {
       name: this.initialName,
       hotkey: this.initialHotkey,
-      source: JSON.stringify(this.initialSource)
+      source: JSON.stringify(this.initialSource),
+      volume: this.initialVolume,
+      fadeIn: this.initialFadeIn,
+      fadeOut: this.initialFadeOut,
+      plays: this.initialPlays,
+      pitch: this.initialPitch,
+      speed: this.initialSpeed,
+      reverse: this.initialReverse,
+      devices: this.initialDevices,
+      tags: this.initialTags,
+      description: this.initialDescription,
+      color: this.initialColor,
+      icon: this.initialIcon
     };
   },
   template: `
-        <form @submit.prevent="$emit('submit', {name: name, hotkey: hotkey, source: source});  $parent.close();">
+        <form @submit.prevent="$emit('submit', {name: name, hotkey: hotkey, source: source, volume: volume, fadeIn: fadeIn, fadeOut: fadeOut, plays: plays, pitch: pitch, speed: speed, reverse: reverse, devices: devices, tags: tags, description: description, color: color, icon: icon});  $parent.close();">
             <div class="modal-card">
                 <header class="modal-card-head">
                     <p class="modal-card-title">Edit sound</p>
@@ -34,6 +46,67 @@ const ModalForm = {
                         <b-input :value="hotkey" v-model="hotkey">
                         </b-input>
                     </b-field>
+
+                    <b-field label="Volume">
+                        <b-slider :step="0.05" :min="0.0" :max="2.0" v-model="volume"></b-slider>
+                    </b-field>
+
+                    <b-field grouped>
+                        <b-field label="Fade in (s)" label-position="on-border" expanded>
+                            <b-numberinput v-model="fadeIn" :min="0" :step="0.1" :min-step="0.01" controls-position="compact" placeholder="default">
+                            </b-numberinput>
+                        </b-field>
+                        <b-field label="Fade out (s)" label-position="on-border" expanded>
+                            <b-numberinput v-model="fadeOut" :min="0" :step="0.1" :min-step="0.01" controls-position="compact" placeholder="default">
+                            </b-numberinput>
+                        </b-field>
+                    </b-field>
+
+                    <b-field label="Plays (0 loops until stopped)" label-position="on-border">
+                        <b-numberinput v-model="plays" :min="0" :step="1" controls-position="compact">
+                        </b-numberinput>
+                    </b-field>
+
+                    <b-field grouped>
+                        <b-field label="Pitch (semitones)" label-position="on-border" expanded>
+                            <b-numberinput v-model="pitch" :min="-24" :max="24" :step="1" :min-step="0.1" controls-position="compact" placeholder="0">
+                            </b-numberinput>
+                        </b-field>
+                        <b-field label="Speed" label-position="on-border" expanded>
+                            <b-numberinput v-model="speed" :min="0.25" :max="4" :step="0.25" :min-step="0.01" controls-position="compact" placeholder="1">
+                            </b-numberinput>
+                        </b-field>
+                    </b-field>
+
+                    <b-field>
+                        <b-checkbox v-model="reverse">Reverse</b-checkbox>
+                    </b-field>
+
+                    <b-field label="Devices (Both, Output, Loop or routing targets)" label-position="on-border">
+                        <b-input v-model="devices" placeholder="soundboard default">
+                        </b-input>
+                    </b-field>
+
+                    <b-field label="Tags" label-position="on-border">
+                        <b-taginput v-model="tags" placeholder="add a tag">
+                        </b-taginput>
+                    </b-field>
+
+                    <b-field label="Description" label-position="on-border">
+                        <b-input v-model="description">
+                        </b-input>
+                    </b-field>
+
+                    <b-field grouped>
+                        <b-field label="Color" label-position="on-border" expanded>
+                            <b-input v-model="color" placeholder="#ff8800">
+                            </b-input>
+                        </b-field>
+                        <b-field label="Icon (emoji or image path)" label-position="on-border" expanded>
+                            <b-input v-model="icon">
+                            </b-input>
+                        </b-field>
+                    </b-field>
                 </section>
                 <footer class="modal-card-foot" style="display: block;">
                   <nav class="level">
@@ -42,6 +115,9 @@ const ModalForm = {
                         <div class="level-item">
                             <button class="button is-danger" type="button" @click="$emit('delete'); $parent.close();">Delete</button>
                         </div>
+                        <div class="level-item" v-if="exportUrl">
+                            <a class="button" :href="exportUrl" download>Export</a>
+                        </div>
                     </div>
                     <div class="level-right">
                         <div class="level-item">
@@ -58,17 +134,102 @@ const ModalForm = {
     `
 }
 
+const DevicesForm = {
+  props: ['initialInput', 'initialOutput', 'initialLoopback', 'missing', 'monitorOnly', 'error', 'devices'],
+  data: function () {
+    return {
+      input: this.initialInput ? this.initialInput : '',
+      output: this.initialOutput ? this.initialOutput : '',
+      loopback: this.initialLoopback ? this.initialLoopback : ''
+    };
+  },
+  computed: {
+    inputDevices: function () {
+      return this.devices.filter((device) => device.type == 'input');
+    },
+    outputDevices: function () {
+      return this.devices.filter((device) => device.type == 'output');
+    }
+  },
+  template: `
+        <form @submit.prevent="$emit('submit', {input: input, output: output, loopback: loopback});  $parent.close();">
+            <div class="modal-card">
+                <header class="modal-card-head">
+                    <p class="modal-card-title">Devices</p>
+                </header>
+                <section class="modal-card-body">
+                    <b-notification v-if="error" type="is-warning" :closable="false">
+                        Audio devices failed: {{ error }}
+                    </b-notification>
+                    <b-notification v-else-if="missing.length > 0" type="is-warning" :closable="false">
+                        Missing devices: {{ missing.join(', ') }}
+                    </b-notification>
+                    <b-notification v-else-if="monitorOnly" type="is-warning" :closable="false">
+                        No loop device, sounds are only played on the output device
+                    </b-notification>
+
+                    <b-field label="Input" label-position="on-border">
+                        <b-select v-model="input" expanded>
+                            <option value="">default input device</option>
+                            <option v-for="device in inputDevices" :key="device.id" :value="device.name">
+                                {{ device.name }}{{ device.default ? ' (default)' : '' }}
+                            </option>
+                        </b-select>
+                    </b-field>
+
+                    <b-field label="Output" label-position="on-border">
+                        <b-select v-model="output" expanded>
+                            <option value="">default output device</option>
+                            <option v-for="device in outputDevices" :key="device.id" :value="device.name">
+                                {{ device.name }}{{ device.default ? ' (default)' : '' }}
+                            </option>
+                        </b-select>
+                    </b-field>
+
+                    <b-field label="Loopback" label-position="on-border">
+                        <b-select v-model="loopback" expanded>
+                            <option value="">no loop device (monitor only)</option>
+                            <option v-if="loopback && !outputDevices.some((device) => device.name == loopback)" :value="loopback">
+                                {{ loopback }} (missing)
+                            </option>
+                            <option v-for="device in outputDevices" :key="device.id" :value="device.name">
+                                {{ device.name }}
+                            </option>
+                        </b-select>
+                    </b-field>
+                </section>
+                <footer class="modal-card-foot" style="display: block;">
+                  <nav class="level">
+                    <div class="level-left"></div>
+                    <div class="level-right">
+                        <div class="level-item">
+                            <button class="button" type="button" @click="$parent.close()">Cancel</button>
+                        </div>
+                        <div class="level-item">
+                            <button class="button is-primary">Switch</button>
+                        </div>
+                    </div>
+                  </nav>
+                </footer>
+            </div>
+        </form>
+    `
+}
+
 var app = new Vue({
   el: '#app',
-  components: { ModalForm },
+  components: { ModalForm, DevicesForm },
   data: {
     activeSounds: [],
+    queue: [],
     soundboards: [],
     soundNames: [],
     matchedSoundNames: [],
     filter: '',
-    volume: 1.0,
-    selectedDevice: 'Both',
+    volumes: { output: 1.0, loopback: 1.0, input: 1.0 },
+    selectedDevice: '',
+    routingTargets: [],
+    deviceStatus: { devices: {}, missing: [], monitor_only: false, error: null },
     showBottomMenu: true,
     showStatusModal: false,
     showLoadingModal: true,
@@ -84,11 +245,6 @@ var app = new Vue({
       fuzzysort.go(val, this.soundNames, { allowTypo: true, threshold: -25000 })
         .forEach((s) => this.matchedSoundNames.set(s.target, s));
     },
-    volume: function (val, oldVal) {
-      axios.post('/api/sounds/volume', { volume: val }).catch(function (error) {
-        self.showStatusModal = true;
-      });
-    },
     showBottomMenu: function (val, oldVal) {
       if (val) {
         document.querySelector('nav').classList.remove('hide_bottom_menu');
@@ -111,12 +267,18 @@ var app = new Vue({
     //     soundboard.sounds.sort((a, b) => a.id.localeCompare(b.id));
     //   }
     // },
+    setVolume(target, volume) {
+      axios.post('/api/sounds/volume', { volume: volume, target: target }).catch((error) => {
+        this.showStatusModal = true;
+      });
+    },
     createEventSources() {
       this.soundEvents = new EventSource('/api/sounds/events');
       this.soundEvents.onmessage = (event) => {
         let play_data = JSON.parse(event.data);
         this.activeSounds = play_data.sounds;
-        this.volume = play_data.volume;
+        this.queue = play_data.queue;
+        this.volumes = play_data.volumes;
         if (this.showStatusModal) {
           this.registeredHotkeys.clear();
           this.reloadData();
@@ -135,11 +297,21 @@ var app = new Vue({
         }, 1000);
       };
 
+      this.soundboardEvents = new EventSource('/api/soundboards/events');
+      this.soundboardEvents.onmessage = (event) => {
+        this.registeredHotkeys.clear();
+        this.reloadData();
+      };
+      this.soundboardEvents.onerror = (err) => {
+        this.soundboardEvents.close();
+      };
+
       this.hotkeyEvents = new EventSource('/api/hotkeys/events');
       this.hotkeyEvents.onmessage = (event) => {
         let sound_data = this.registeredHotkeys.get(event.data);
         if (sound_data.special === 'STOPALL') return this.stopAllSound();
-        this.playSound(sound_data.soundboard_id, sound_data.sound_id);
+        // hotkeys always use the routing of the sound
+        this.playSound(sound_data.soundboard_id, sound_data.sound_id, '');
       };
 
       this.hotkeyEvents.onerror = (err) => {
@@ -189,11 +361,30 @@ var app = new Vue({
         .catch((error) => {
           self.showStatusModal = true;
         });
+      axios.get('/api/targets')
+        .then((response) => {
+          this.routingTargets = response.data.data;
+        });
+      this.loadDeviceStatus();
+    },
+    loadDeviceStatus: function () {
+      axios.get('/api/devices/current')
+        .then((response) => {
+          this.deviceStatus = response.data.data;
+        });
     },
     hideKeyboard() {
       document.activeElement.blur();
     },
     showResponseError(text, err) {
+      if (err.response && err.response.status === 409) {
+        // the soundboard changed on disk, show the current state with the conflicts
+        this.showWarning(text + ' conflict: ' +
+          err.response.data.errors.map((e) => e.detail).join(', '));
+        this.registeredHotkeys.clear();
+        this.reloadData();
+        return;
+      }
       this.showWarning(
         text + ' error: ' +
         (err.response ? JSON.stringify(err.response.data.errors) :
@@ -239,13 +430,13 @@ var app = new Vue({
           this.showResponseError('deregisterHotkey', error);
         });
     },
-    playSound: function (soundboard_id, sound_id) {
+    playSound: function (soundboard_id, sound_id, devices = this.selectedDevice) {
       axios
         .post(
           '/api/soundboards/' + soundboard_id + '/sounds/' + sound_id +
           '/play',
           {
-            devices: this.selectedDevice,
+            devices: devices ? devices : null,
           })
         .then((response) => (this.lastRequestAnswer = response.data.data));
     },
@@ -260,6 +451,46 @@ var app = new Vue({
       axios.post('/api/sounds/stopall')
         .then((response) => (this.lastRequestAnswer = response.data.data));
     },
+    pauseSound: function (sound_id) {
+      axios.post('/api/sounds/' + sound_id + '/pause')
+        .then((response) => (this.lastRequestAnswer = response.data.data));
+    },
+    resumeSound: function (sound_id) {
+      axios.post('/api/sounds/' + sound_id + '/resume')
+        .then((response) => (this.lastRequestAnswer = response.data.data));
+    },
+    seekSound: function (sound_id, position) {
+      axios.post('/api/sounds/' + sound_id + '/seek', { position: position })
+        .then((response) => (this.lastRequestAnswer = response.data.data));
+    },
+    pauseAllSound: function () {
+      axios.post('/api/sounds/pauseall')
+        .then((response) => (this.lastRequestAnswer = response.data.data));
+    },
+    resumeAllSound: function () {
+      axios.post('/api/sounds/resumeall')
+        .then((response) => (this.lastRequestAnswer = response.data.data));
+    },
+    enqueueSound: function (sound_id) {
+      axios.post('/api/queue', { sound_id: sound_id, devices: this.selectedDevice ? this.selectedDevice : null })
+        .then((response) => (this.lastRequestAnswer = response.data.data));
+    },
+    skipQueue: function (devices) {
+      axios.post('/api/queue/' + devices + '/skip')
+        .then((response) => (this.lastRequestAnswer = response.data.data));
+    },
+    clearQueue: function (devices) {
+      axios.delete('/api/queue/' + devices)
+        .then((response) => (this.lastRequestAnswer = response.data.data));
+    },
+    moveInQueue: function (devices, from, to) {
+      axios.post('/api/queue/' + devices + '/move', { from: from, to: to })
+        .then((response) => (this.lastRequestAnswer = response.data.data));
+    },
+    removeFromQueue: function (devices, index) {
+      axios.delete('/api/queue/' + devices + '/' + index)
+        .then((response) => (this.lastRequestAnswer = response.data.data));
+    },
     addSoundFromPaste: function (soundboard_id, event) {
       event.preventDefault();
 
@@ -415,6 +646,43 @@ var app = new Vue({
           this.reloadData();
         });
     },
+    editDevices: function () {
+      Promise.all([axios.get('/api/devices/current'), axios.get('/api/devices')])
+        .then(([statusResponse, devicesResponse]) => {
+          let status = statusResponse.data.data;
+          this.$buefy.modal.open({
+            parent: this,
+            component: DevicesForm,
+            hasModalCard: true,
+            trapFocus: true,
+            props: {
+              'initialInput': status.devices.input,
+              'initialOutput': status.devices.output,
+              'initialLoopback': status.devices.loopback,
+              'missing': status.missing,
+              'monitorOnly': status.monitor_only,
+              'error': status.error,
+              'devices': devicesResponse.data.data
+            },
+            events: {
+              'submit': (new_data) => {
+                axios.post('/api/devices/current', {
+                  input: new_data.input ? new_data.input : null,
+                  output: new_data.output ? new_data.output : null,
+                  loopback: new_data.loopback ? new_data.loopback : null,
+                })
+                  .then((response) => {
+                    this.showSuccess('switched devices');
+                    // the sound thread switches the devices asynchronously
+                    setTimeout(() => this.loadDeviceStatus(), 1000);
+                  })
+                  .catch((error) => this.showResponseError('switchDevices', error));
+              }
+            }
+          });
+        })
+        .catch((error) => this.showResponseError('editDevices', error));
+    },
     editSound: function (soundboard_id, sound_id) {
       let soundboard = this.soundboards.find((s) => s.id === soundboard_id);
       if (!soundboard) return;
@@ -424,7 +692,20 @@ var app = new Vue({
       let props = {
         'initialName': sound.name,
         'initialHotkey': sound.hotkey,
-        'initialSource': sound.source
+        'initialSource': sound.source,
+        'initialVolume': sound.volume != null ? sound.volume : 1.0,
+        'initialFadeIn': sound.fade_in,
+        'initialFadeOut': sound.fade_out,
+        'initialPlays': sound.loop === true ? 0 : (typeof sound.loop === 'number' ? sound.loop : 1),
+        'initialPitch': sound.pitch,
+        'initialSpeed': sound.speed,
+        'initialReverse': sound.reverse === true,
+        'initialDevices': sound.devices,
+        'initialTags': sound.tags ? sound.tags.slice() : [],
+        'initialDescription': sound.description,
+        'initialColor': sound.color,
+        'initialIcon': sound.icon,
+        'exportUrl': '/api/soundboards/' + soundboard_id + '/sounds/' + sound_id + '/export'
       };
 
       this.$buefy.modal.open({
@@ -462,6 +743,18 @@ var app = new Vue({
           name: new_data.name,
           hotkey: new_data.hotkey,
           source: new_data.source,
+          volume: new_data.volume,
+          fade_in: new_data.fadeIn,
+          fade_out: new_data.fadeOut,
+          loop: new_data.plays === 0 ? true : (new_data.plays > 1 ? new_data.plays : null),
+          pitch: new_data.pitch ? new_data.pitch : null,
+          speed: new_data.speed && new_data.speed !== 1 ? new_data.speed : null,
+          reverse: new_data.reverse ? true : null,
+          devices: new_data.devices ? new_data.devices : null,
+          tags: new_data.tags && new_data.tags.length ? new_data.tags : null,
+          description: new_data.description ? new_data.description : null,
+          color: new_data.color ? new_data.color : null,
+          icon: new_data.icon ? new_data.icon : null,
         })
         .then((response) => {
           let sound = response.data.data;
@@ -557,6 +850,37 @@ var app = new Vue({
           this.reloadData();
         });
     },
+    soundMatchesFilter: function (sound) {
+      if (!this.filter.length || this.matchedSoundNames.has(sound.name)) return true;
+      let filter = this.filter.toLowerCase();
+      return (sound.tags || []).some((tag) => tag.toLowerCase().includes(filter));
+    },
+    soundLabel: function (sound) {
+      if (!this.filter.length || !this.matchedSoundNames.has(sound.name)) return sound.name;
+      return fuzzysort.highlight(this.matchedSoundNames.get(sound.name));
+    },
+    soundStyle: function (sound) {
+      return sound.color ? { backgroundColor: sound.color } : {};
+    },
+    isImageIcon: function (icon) {
+      return !!icon && /\.\w+$/.test(icon);
+    },
+    soundIconUrl: function (soundboard_id, sound) {
+      // the icon path in the query reloads the image when the icon changes
+      return '/api/soundboards/' + soundboard_id + '/sounds/' + sound.id + '/icon?' +
+        encodeURIComponent(sound.icon);
+    },
+    restoreSoundboard: function (soundboard_id, action) {
+      axios
+        .post('/api/soundboards/' + soundboard_id + '/' + action)
+        .then((response) => {
+          this.showSuccess(action + ': ' + response.data.data.name);
+          this.reloadData();
+        })
+        .catch((error) => {
+          this.showResponseError(action, error);
+        });
+    },
   },
 });
 